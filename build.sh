@@ -1,2 +1,2 @@
-wasm-pack build --no-typescript --release -d docs/pkg --target web
+wasm-pack build --no-typescript --release -d docs/pkg --target web --out-name wasm_lib -- --no-default-features --features wasm
 rm docs/pkg/.gitignore
@@ -1,98 +1,268 @@
+/**
+ * @enum {0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 | 11 | 12 | 13}
+ */
+export const SiftError = Object.freeze({
+    InvalidRegExp: 0, "0": "InvalidRegExp",
+    InvalidCharacters: 1, "1": "InvalidCharacters",
+    InvalidCommand: 2, "2": "InvalidCommand",
+    MissingLetters: 3, "3": "MissingLetters",
+    InvalidNumber: 4, "4": "InvalidNumber",
+    FileIOError: 5, "5": "FileIOError",
+    SerializationError: 6, "6": "SerializationError",
+    DeserializationError: 7, "7": "DeserializationError",
+    InvalidMapping: 8, "8": "InvalidMapping",
+    InvalidGrid: 9, "9": "InvalidGrid",
+    InvalidGuess: 10, "10": "InvalidGuess",
+    InvalidTemplate: 11, "11": "InvalidTemplate",
+    MissingStage: 12, "12": "MissingStage",
+    UnknownWord: 13, "13": "UnknownWord",
+});
 
-let wasm;
-
-const heap = new Array(32).fill(undefined);
-
-heap.push(undefined, null, true, false);
+export class Sifter {
+    static __wrap(ptr) {
+        const obj = Object.create(Sifter.prototype);
+        obj.__wbg_ptr = ptr;
+        SifterFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        SifterFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_sifter_free(ptr, 0);
+    }
+}
+if (Symbol.dispose) Sifter.prototype[Symbol.dispose] = Sifter.prototype.free;
 
-function getObject(idx) { return heap[idx]; }
+export class SifterResult {
+    static __wrap(ptr) {
+        const obj = Object.create(SifterResult.prototype);
+        obj.__wbg_ptr = ptr;
+        SifterResultFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        SifterResultFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_sifterresult_free(ptr, 0);
+    }
+    /**
+     * How many results there were, including those from `wasm_count`, which
+     * doesn't keep the results themselves.
+     * @returns {number}
+     */
+    count() {
+        const ret = wasm.sifterresult_count(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {boolean}
+     */
+    is_empty() {
+        const ret = wasm.sifterresult_is_empty(this.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * @returns {number}
+     */
+    len() {
+        const ret = wasm.sifterresult_len(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * Whether the query stopped early at one of its limits, so some results
+     * may be missing.
+     * @returns {boolean}
+     */
+    partial() {
+        const ret = wasm.sifterresult_partial(this.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * @param {number} limit
+     * @returns {string}
+     */
+    to_string(limit) {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.sifterresult_to_string(this.__wbg_ptr, limit);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
+    }
+}
+if (Symbol.dispose) SifterResult.prototype[Symbol.dispose] = SifterResult.prototype.free;
 
-let heap_next = heap.length;
+export function init_panic_hook() {
+    wasm.init_panic_hook();
+}
 
-function dropObject(idx) {
-    if (idx < 36) return;
-    heap[idx] = heap_next;
-    heap_next = idx;
+/**
+ * Counts the results of a query without formatting them, within the same
+ * limits as `wasm_sift`.
+ * @param {string} args
+ * @param {Sifter} sifter
+ * @param {number | null} [max_patterns]
+ * @param {number | null} [timeout_ms]
+ * @param {boolean | null} [raw]
+ * @returns {SifterResult}
+ */
+export function wasm_count(args, sifter, max_patterns, timeout_ms, raw) {
+    const ptr0 = passStringToWasm0(args, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    const len0 = WASM_VECTOR_LEN;
+    _assertClass(sifter, Sifter);
+    const ret = wasm.wasm_count(ptr0, len0, sifter.__wbg_ptr, isLikeNone(max_patterns) ? Number.MAX_SAFE_INTEGER : (max_patterns) >>> 0, isLikeNone(timeout_ms) ? Number.MAX_SAFE_INTEGER : (timeout_ms) >>> 0, isLikeNone(raw) ? 0xFFFFFF : raw ? 1 : 0);
+    return SifterResult.__wrap(ret);
 }
 
-function takeObject(idx) {
-    const ret = getObject(idx);
-    dropObject(idx);
-    return ret;
+/**
+ * @param {Uint8Array} dict_data
+ * @returns {Sifter}
+ */
+export function wasm_get_sifter(dict_data) {
+    const ptr0 = passArray8ToWasm0(dict_data, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ret = wasm.wasm_get_sifter(ptr0, len0);
+    return Sifter.__wrap(ret);
 }
 
-let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+/**
+ * Runs a query, stopping early with the results so far once it has tried
+ * `max_patterns` patterns in one command, found `max_results` results or run
+ * for `timeout_ms`.
+ * @param {string} args
+ * @param {Sifter} sifter
+ * @param {number | null} [max_patterns]
+ * @param {number | null} [max_results]
+ * @param {number | null} [timeout_ms]
+ * @param {boolean | null} [raw]
+ * @returns {SifterResult}
+ */
+export function wasm_sift(args, sifter, max_patterns, max_results, timeout_ms, raw) {
+    const ptr0 = passStringToWasm0(args, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    const len0 = WASM_VECTOR_LEN;
+    _assertClass(sifter, Sifter);
+    const ret = wasm.wasm_sift(ptr0, len0, sifter.__wbg_ptr, isLikeNone(max_patterns) ? Number.MAX_SAFE_INTEGER : (max_patterns) >>> 0, isLikeNone(max_results) ? Number.MAX_SAFE_INTEGER : (max_results) >>> 0, isLikeNone(timeout_ms) ? Number.MAX_SAFE_INTEGER : (timeout_ms) >>> 0, isLikeNone(raw) ? 0xFFFFFF : raw ? 1 : 0);
+    return SifterResult.__wrap(ret);
+}
+function __wbg_get_imports() {
+    const import0 = {
+        __proto__: null,
+        __wbg___wbindgen_throw_41e9ee4f547fc59a: function(arg0, arg1) {
+            throw new Error(getStringFromWasm0(arg0, arg1));
+        },
+        __wbg_error_757e9472f8410341: function(arg0, arg1) {
+            let deferred0_0;
+            let deferred0_1;
+            try {
+                deferred0_0 = arg0;
+                deferred0_1 = arg1;
+                console.error(getStringFromWasm0(arg0, arg1));
+            } finally {
+                wasm.__wbindgen_free(deferred0_0, deferred0_1, 1);
+            }
+        },
+        __wbg_new_227d7c05414eb861: function() {
+            const ret = new Error();
+            return ret;
+        },
+        __wbg_now_5a2e443db600e30a: function() {
+            const ret = Date.now();
+            return ret;
+        },
+        __wbg_stack_3b0d974bbf31e44f: function(arg0, arg1) {
+            const ret = arg1.stack;
+            const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+        },
+        __wbindgen_init_externref_table: function() {
+            const table = wasm.__wbindgen_externrefs;
+            const offset = table.grow(4);
+            table.set(0, undefined);
+            table.set(offset + 0, undefined);
+            table.set(offset + 1, null);
+            table.set(offset + 2, true);
+            table.set(offset + 3, false);
+        },
+    };
+    return {
+        __proto__: null,
+        "./wasm_lib_bg.js": import0,
+    };
+}
 
-cachedTextDecoder.decode();
+const SifterFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_sifter_free(ptr, 1));
+const SifterResultFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_sifterresult_free(ptr, 1));
 
-let cachegetUint8Memory0 = null;
-function getUint8Memory0() {
-    if (cachegetUint8Memory0 === null || cachegetUint8Memory0.buffer !== wasm.memory.buffer) {
-        cachegetUint8Memory0 = new Uint8Array(wasm.memory.buffer);
+function _assertClass(instance, klass) {
+    if (!(instance instanceof klass)) {
+        throw new Error(`expected instance of ${klass.name}`);
     }
-    return cachegetUint8Memory0;
 }
 
-function getStringFromWasm0(ptr, len) {
-    return cachedTextDecoder.decode(getUint8Memory0().subarray(ptr, ptr + len));
+let cachedDataViewMemory0 = null;
+function getDataViewMemory0() {
+    if (cachedDataViewMemory0 === null || cachedDataViewMemory0.buffer.detached === true || (cachedDataViewMemory0.buffer.detached === undefined && cachedDataViewMemory0.buffer !== wasm.memory.buffer)) {
+        cachedDataViewMemory0 = new DataView(wasm.memory.buffer);
+    }
+    return cachedDataViewMemory0;
 }
 
-let WASM_VECTOR_LEN = 0;
-
-function passArray8ToWasm0(arg, malloc) {
-    const ptr = malloc(arg.length * 1);
-    getUint8Memory0().set(arg, ptr / 1);
-    WASM_VECTOR_LEN = arg.length;
-    return ptr;
-}
-/**
-* @param {Uint8Array} dict_data
-* @returns {Sifter}
-*/
-export function wasm_get_sifter(dict_data) {
-    var ptr0 = passArray8ToWasm0(dict_data, wasm.__wbindgen_malloc);
-    var len0 = WASM_VECTOR_LEN;
-    var ret = wasm.wasm_get_sifter(ptr0, len0);
-    return Sifter.__wrap(ret);
+function getStringFromWasm0(ptr, len) {
+    return decodeText(ptr >>> 0, len);
 }
 
-let cachegetInt32Memory0 = null;
-function getInt32Memory0() {
-    if (cachegetInt32Memory0 === null || cachegetInt32Memory0.buffer !== wasm.memory.buffer) {
-        cachegetInt32Memory0 = new Int32Array(wasm.memory.buffer);
+let cachedUint8ArrayMemory0 = null;
+function getUint8ArrayMemory0() {
+    if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
+        cachedUint8ArrayMemory0 = new Uint8Array(wasm.memory.buffer);
     }
-    return cachegetInt32Memory0;
+    return cachedUint8ArrayMemory0;
 }
 
-let cachedTextEncoder = new TextEncoder('utf-8');
+function isLikeNone(x) {
+    return x === undefined || x === null;
+}
 
-const encodeString = (typeof cachedTextEncoder.encodeInto === 'function'
-    ? function (arg, view) {
-    return cachedTextEncoder.encodeInto(arg, view);
+function passArray8ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 1, 1) >>> 0;
+    getUint8ArrayMemory0().set(arg, ptr / 1);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
 }
-    : function (arg, view) {
-    const buf = cachedTextEncoder.encode(arg);
-    view.set(buf);
-    return {
-        read: arg.length,
-        written: buf.length
-    };
-});
 
 function passStringToWasm0(arg, malloc, realloc) {
-
     if (realloc === undefined) {
         const buf = cachedTextEncoder.encode(arg);
-        const ptr = malloc(buf.length);
-        getUint8Memory0().subarray(ptr, ptr + buf.length).set(buf);
+        const ptr = malloc(buf.length, 1) >>> 0;
+        getUint8ArrayMemory0().subarray(ptr, ptr + buf.length).set(buf);
         WASM_VECTOR_LEN = buf.length;
         return ptr;
     }
 
     let len = arg.length;
-    let ptr = malloc(len);
+    let ptr = malloc(len, 1) >>> 0;
 
-    const mem = getUint8Memory0();
+    const mem = getUint8ArrayMemory0();
 
     let offset = 0;
 
@@ -101,203 +271,145 @@ function passStringToWasm0(arg, malloc, realloc) {
         if (code > 0x7F) break;
         mem[ptr + offset] = code;
     }
-
     if (offset !== len) {
         if (offset !== 0) {
             arg = arg.slice(offset);
         }
-        ptr = realloc(ptr, len, len = offset + arg.length * 3);
-        const view = getUint8Memory0().subarray(ptr + offset, ptr + len);
-        const ret = encodeString(arg, view);
+        ptr = realloc(ptr, len, len = offset + arg.length * 3, 1) >>> 0;
+        const view = getUint8ArrayMemory0().subarray(ptr + offset, ptr + len);
+        const ret = cachedTextEncoder.encodeInto(arg, view);
 
         offset += ret.written;
+        ptr = realloc(ptr, len, offset, 1) >>> 0;
     }
 
     WASM_VECTOR_LEN = offset;
     return ptr;
 }
 
-function _assertClass(instance, klass) {
-    if (!(instance instanceof klass)) {
-        throw new Error(`expected instance of ${klass.name}`);
+let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+cachedTextDecoder.decode();
+const MAX_SAFARI_DECODE_BYTES = 2146435072;
+let numBytesDecoded = 0;
+function decodeText(ptr, len) {
+    numBytesDecoded += len;
+    if (numBytesDecoded >= MAX_SAFARI_DECODE_BYTES) {
+        cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+        cachedTextDecoder.decode();
+        numBytesDecoded = len;
     }
-    return instance.ptr;
-}
-/**
-* @param {string} args
-* @param {Sifter} sifter
-* @returns {SifterResult}
-*/
-export function wasm_sift(args, sifter) {
-    var ptr0 = passStringToWasm0(args, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-    var len0 = WASM_VECTOR_LEN;
-    _assertClass(sifter, Sifter);
-    var ret = wasm.wasm_sift(ptr0, len0, sifter.ptr);
-    return SifterResult.__wrap(ret);
-}
-
-/**
-*/
-export function init_panic_hook() {
-    wasm.init_panic_hook();
+    return cachedTextDecoder.decode(getUint8ArrayMemory0().subarray(ptr, ptr + len));
 }
 
-function addHeapObject(obj) {
-    if (heap_next === heap.length) heap.push(heap.length + 1);
-    const idx = heap_next;
-    heap_next = heap[idx];
+const cachedTextEncoder = new TextEncoder();
 
-    heap[idx] = obj;
-    return idx;
+if (!('encodeInto' in cachedTextEncoder)) {
+    cachedTextEncoder.encodeInto = function (arg, view) {
+        const buf = cachedTextEncoder.encode(arg);
+        view.set(buf);
+        return {
+            read: arg.length,
+            written: buf.length
+        };
+    };
 }
-/**
-*/
-export const SiftError = Object.freeze({ InvalidRegExp:0,"0":"InvalidRegExp",InvalidCharacters:1,"1":"InvalidCharacters",InvalidCommand:2,"2":"InvalidCommand",MissingLetters:3,"3":"MissingLetters",InvalidNumber:4,"4":"InvalidNumber",FileIOError:5,"5":"FileIOError",SerializationError:6,"6":"SerializationError",DeserializationError:7,"7":"DeserializationError", });
-/**
-*/
-export class Sifter {
-
-    static __wrap(ptr) {
-        const obj = Object.create(Sifter.prototype);
-        obj.ptr = ptr;
-
-        return obj;
-    }
 
-    __destroy_into_raw() {
-        const ptr = this.ptr;
-        this.ptr = 0;
-
-        return ptr;
-    }
+let WASM_VECTOR_LEN = 0;
 
-    free() {
-        const ptr = this.__destroy_into_raw();
-        wasm.__wbg_sifter_free(ptr);
-    }
+let wasmModule, wasmInstance, wasm;
+function __wbg_finalize_init(instance, module) {
+    wasmInstance = instance;
+    wasm = instance.exports;
+    wasmModule = module;
+    cachedDataViewMemory0 = null;
+    cachedUint8ArrayMemory0 = null;
+    wasm.__wbindgen_start();
+    return wasm;
 }
-/**
-*/
-export class SifterResult {
-
-    static __wrap(ptr) {
-        const obj = Object.create(SifterResult.prototype);
-        obj.ptr = ptr;
-
-        return obj;
-    }
-
-    __destroy_into_raw() {
-        const ptr = this.ptr;
-        this.ptr = 0;
-
-        return ptr;
-    }
 
-    free() {
-        const ptr = this.__destroy_into_raw();
-        wasm.__wbg_sifterresult_free(ptr);
-    }
-    /**
-    * @returns {number}
-    */
-    len() {
-        var ret = wasm.sifterresult_len(this.ptr);
-        return ret >>> 0;
-    }
-    /**
-    * @param {number} limit
-    * @returns {string}
-    */
-    to_string(limit) {
-        try {
-            const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
-            wasm.sifterresult_to_string(retptr, this.ptr, limit);
-            var r0 = getInt32Memory0()[retptr / 4 + 0];
-            var r1 = getInt32Memory0()[retptr / 4 + 1];
-            return getStringFromWasm0(r0, r1);
-        } finally {
-            wasm.__wbindgen_add_to_stack_pointer(16);
-            wasm.__wbindgen_free(r0, r1);
+async function __wbg_load(module, imports) {
+    if (typeof Response === 'function' && module instanceof Response) {
+        if (!module.ok) {
+            throw new Error(`failed to fetch Wasm: ${module.status} ${module.statusText} fetching '${module.url}'`);
         }
-    }
-}
 
-async function load(module, imports) {
-    if (typeof Response === 'function' && module instanceof Response) {
         if (typeof WebAssembly.instantiateStreaming === 'function') {
             try {
                 return await WebAssembly.instantiateStreaming(module, imports);
-
             } catch (e) {
-                if (module.headers.get('Content-Type') != 'application/wasm') {
-                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
+                const validResponse = expectedResponseType(module.type);
+
+                if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
+                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
 
-                } else {
-                    throw e;
-                }
+                } else { throw e; }
             }
         }
 
         const bytes = await module.arrayBuffer();
         return await WebAssembly.instantiate(bytes, imports);
-
     } else {
         const instance = await WebAssembly.instantiate(module, imports);
 
         if (instance instanceof WebAssembly.Instance) {
             return { instance, module };
-
         } else {
             return instance;
         }
     }
-}
 
-async function init(input) {
-    if (typeof input === 'undefined') {
-        input = new URL('wasm_lib_bg.wasm', import.meta.url);
+    function expectedResponseType(type) {
+        switch (type) {
+            case 'basic': case 'cors': case 'default': return true;
+        }
+        return false;
     }
-    const imports = {};
-    imports.wbg = {};
-    imports.wbg.__wbg_new_59cb74e423758ede = function() {
-        var ret = new Error();
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_stack_558ba5917b466edd = function(arg0, arg1) {
-        var ret = getObject(arg1).stack;
-        var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len0 = WASM_VECTOR_LEN;
-        getInt32Memory0()[arg0 / 4 + 1] = len0;
-        getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-    };
-    imports.wbg.__wbg_error_4bb6c2a97407129a = function(arg0, arg1) {
-        try {
-            console.error(getStringFromWasm0(arg0, arg1));
-        } finally {
-            wasm.__wbindgen_free(arg0, arg1);
+}
+
+function initSync(module) {
+    if (wasm !== undefined) return wasm;
+
+
+    if (module !== undefined) {
+        if (Object.getPrototypeOf(module) === Object.prototype) {
+            ({module} = module)
+        } else {
+            console.warn('using deprecated parameters for `initSync()`; pass a single object instead')
         }
-    };
-    imports.wbg.__wbindgen_object_drop_ref = function(arg0) {
-        takeObject(arg0);
-    };
-    imports.wbg.__wbindgen_throw = function(arg0, arg1) {
-        throw new Error(getStringFromWasm0(arg0, arg1));
-    };
+    }
 
-    if (typeof input === 'string' || (typeof Request === 'function' && input instanceof Request) || (typeof URL === 'function' && input instanceof URL)) {
-        input = fetch(input);
+    const imports = __wbg_get_imports();
+    if (!(module instanceof WebAssembly.Module)) {
+        module = new WebAssembly.Module(module);
     }
+    const instance = new WebAssembly.Instance(module, imports);
+    return __wbg_finalize_init(instance, module);
+}
 
+async function __wbg_init(module_or_path) {
+    if (wasm !== undefined) return wasm;
 
 
-    const { instance, module } = await load(await input, imports);
+    if (module_or_path !== undefined) {
+        if (Object.getPrototypeOf(module_or_path) === Object.prototype) {
+            ({module_or_path} = module_or_path)
+        } else {
+            console.warn('using deprecated parameters for the initialization function; pass a single object instead')
+        }
+    }
 
-    wasm = instance.exports;
-    init.__wbindgen_wasm_module = module;
+    if (module_or_path === undefined) {
+        module_or_path = new URL('wasm_lib_bg.wasm', import.meta.url);
+    }
+    const imports = __wbg_get_imports();
 
-    return wasm;
-}
+    if (typeof module_or_path === 'string' || (typeof Request === 'function' && module_or_path instanceof Request) || (typeof URL === 'function' && module_or_path instanceof URL)) {
+        module_or_path = fetch(module_or_path);
+    }
 
-export default init;
+    const { instance, module } = await __wbg_load(await module_or_path, imports);
+
+    return __wbg_finalize_init(instance, module);
+}
 
+export { initSync, __wbg_init as default };
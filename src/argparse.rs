@@ -1,90 +1,858 @@
-use clap::{Arg, App, SubCommand, ArgMatches};
-use regex::Regex;
-use crate::sift_command::SiftCommand;
-use wasm_bindgen::prelude::*;
+use crate::sift_command::{SiftCommand, SortOrder};
+use crate::extract::{Selection, Transform};
+use crate::score::{ScoreScheme, read_mapping};
+use crate::encoding::Encoding;
+use crate::grid::{Grid, GridOptions};
+use crate::crossword::Crossword;
+use crate::wordle::Guess;
+use crate::letterbox::LetterBox;
+use crate::sequence::Gaps;
+use crate::filter::{ResultFilter, whole_word_regex};
+use crate::lexer::{ParseError, Span, Token, TokenKind, tokens_from_args};
+use crate::template::{self, RegexTemplate};
+use crate::error::SiftError;
+use std::fs::File;
+use std::ops::RangeInclusive;
 
-#[wasm_bindgen]
+/// An option a command takes: `--name value`, `-s value`, or a flag.
+#[derive(Clone, Copy, Debug)]
+pub struct OptionSpec {
+    pub name: &'static str,
+    /// Whether it can be given as `--name`, rather than only by its short form.
+    pub long: bool,
+    pub short: Option<char>,
+    pub takes_value: bool,
+    pub default: Option<&'static str>,
+    /// The values it accepts, or any value when empty.
+    pub choices: &'static [&'static str],
+    /// Options it can't be given alongside.
+    pub conflicts: &'static [&'static str],
+    pub help: &'static str,
+}
+
+impl OptionSpec {
+    const fn flag(name: &'static str, help: &'static str) -> OptionSpec {
+        OptionSpec { name, long: true, short: None, takes_value: false, default: None, choices: &[], conflicts: &[], help }
+    }
+
+    const fn value(name: &'static str, help: &'static str) -> OptionSpec {
+        OptionSpec { takes_value: true, ..OptionSpec::flag(name, help) }
+    }
+
+    const fn short(self, short: char) -> OptionSpec {
+        OptionSpec { short: Some(short), ..self }
+    }
+
+    /// Only accepted by its short form.
+    const fn short_only(self, short: char) -> OptionSpec {
+        OptionSpec { long: false, short: Some(short), ..self }
+    }
+
+    const fn default(self, default: &'static str) -> OptionSpec {
+        OptionSpec { default: Some(default), ..self }
+    }
+
+    const fn choices(self, choices: &'static [&'static str]) -> OptionSpec {
+        OptionSpec { choices, ..self }
+    }
+
+    const fn conflicts(self, conflicts: &'static [&'static str]) -> OptionSpec {
+        OptionSpec { conflicts, ..self }
+    }
+
+    /// How the option is written in usage, like `-n <n>` or `--len <len>`.
+    fn usage(&self) -> String {
+        let name = match (self.long, self.short) {
+            (true, _) => format!("--{}", self.name),
+            (false, Some(short)) => format!("-{}", short),
+            (false, None) => self.name.to_string(),
+        };
+        match self.takes_value {
+            true => format!("{} <{}>", name, self.name),
+            false => name,
+        }
+    }
+}
+
+/// An argument a command takes by position.
+#[derive(Clone, Copy, Debug)]
+pub struct PositionalSpec {
+    pub name: &'static str,
+    /// Whether it takes every remaining argument.
+    pub multiple: bool,
+    pub required: bool,
+    pub choices: &'static [&'static str],
+    pub help: &'static str,
+}
+
+impl PositionalSpec {
+    const fn one(name: &'static str, help: &'static str) -> PositionalSpec {
+        PositionalSpec { name, multiple: false, required: false, choices: &[], help }
+    }
+
+    const fn many(name: &'static str, help: &'static str) -> PositionalSpec {
+        PositionalSpec { multiple: true, ..PositionalSpec::one(name, help) }
+    }
+
+    const fn required(self, choices: &'static [&'static str]) -> PositionalSpec {
+        PositionalSpec { required: true, choices, ..self }
+    }
+}
+
+/// A command's name and the arguments it takes.
 #[derive(Debug)]
-pub enum SiftError {
-    InvalidRegExp,
-    InvalidCharacters,
-    InvalidCommand,
-    MissingLetters,
-    InvalidNumber,
-    FileIOError,
-    SerializationError,
-    DeserializationError,
-}
-
-pub fn get_app() -> App<'static, 'static> {
-    let n_arg = Arg::with_name("n")
-        .short("n")
-        .default_value("1")
-        .takes_value(true);
-    let letters_arg = Arg::with_name("letters").index(1);
-    App::new("sift")
-        .arg(Arg::with_name("regex")
-            .help("regular expression")
-            .index(1))
-        .subcommand(SubCommand::with_name("anagram")
-            .about("anagram of the letters")
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("transpose-delete")
-            .alias("td")
-            .about("anagram of the letters after deleting n chars")
-            .arg(n_arg.clone())
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("delete")
-            .about("words achievable after deleting n chars")
-            .arg(n_arg.clone())
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("transpose-add")
-            .alias("ta")
-            .about("words achievable after adding n chars")
-            .arg(n_arg.clone())
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("bank")
-            .about("words using the same set of letters")
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("delete")
-            .about("words achievable by deleting n letters")
-            .arg(n_arg.clone())
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("add")
-            .about("words achievable by adding n letters")
-            .arg(n_arg.clone())
-            .arg(letters_arg.clone()))
-        .subcommand(SubCommand::with_name("change")
-            .about("words achievable by changing n letters")
-            .arg(n_arg.clone())
-            .arg(letters_arg.clone()))
-}
-
-fn get_regex<'a>(matches: &'a ArgMatches) -> Result<Regex, SiftError> {
-    let pattern = matches.value_of("regex").ok_or(SiftError::MissingLetters)?;
-    Regex::new(pattern).map_err(|_| SiftError::InvalidRegExp)
-}
-
-fn get_letters<'a>(matches: &'a ArgMatches) -> Result<String, SiftError> {
-    matches.value_of("letters").ok_or(SiftError::MissingLetters)
-        .and_then(|s| Ok(s.to_string()))
-}
-
-fn get_n(matches: &ArgMatches) -> Result<usize, SiftError> {
-    let n_str = matches.value_of("n").unwrap();
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub about: &'static str,
+    pub options: &'static [OptionSpec],
+    pub positionals: &'static [PositionalSpec],
+    /// Whether arguments starting with '-' that aren't options are taken as
+    /// positional arguments, as in morse code.
+    pub hyphen_values: bool,
+}
+
+impl CommandSpec {
+    fn find_option(&self, matches: impl Fn(&OptionSpec) -> bool) -> Option<&'static OptionSpec> {
+        self.options.iter().chain(FILTER_OPTIONS.iter()).find(|option| matches(option))
+    }
+
+    fn usage(&self) -> String {
+        let mut usage = self.name.to_string();
+        if !self.options.is_empty() {
+            usage.push_str(" [options]");
+        }
+        for positional in self.positionals {
+            let dots = if positional.multiple { "..." } else { "" };
+            match positional.required {
+                true => usage.push_str(&format!(" <{}{}>", positional.name, dots)),
+                false => usage.push_str(&format!(" [{}{}]", positional.name, dots)),
+            }
+        }
+        usage
+    }
+}
+
+const fn command(name: &'static str, about: &'static str, options: &'static [OptionSpec], positionals: &'static [PositionalSpec]) -> CommandSpec {
+    CommandSpec { name, aliases: &[], about, options, positionals, hyphen_values: false }
+}
+
+const fn aliased(aliases: &'static [&'static str], spec: CommandSpec) -> CommandSpec {
+    CommandSpec { aliases, ..spec }
+}
+
+const fn hyphen_values(spec: CommandSpec) -> CommandSpec {
+    CommandSpec { hyphen_values: true, ..spec }
+}
+
+const N: OptionSpec = OptionSpec::value("n", "how many letters").short_only('n').default("1");
+const LETTERS: PositionalSpec = PositionalSpec::one("letters", "letters to use");
+const LETTERS_ONLY: &[PositionalSpec] = &[LETTERS];
+const N_OPTIONS: &[OptionSpec] = &[N];
+const GAP_OPTIONS: [OptionSpec; 2] = [
+    OptionSpec::value("min-gap", "fewest letters skipped between consecutive letters").default("0"),
+    OptionSpec::value("max-gap", "most letters skipped between consecutive letters"),
+];
+const ENCODINGS: &[&str] = &["morse", "braille", "semaphore", "binary", "nato"];
+const CODED: &[PositionalSpec] = &[
+    PositionalSpec::one("encoding", "how the message is encoded").required(ENCODINGS),
+    PositionalSpec::many("message", "the message"),
+];
+const GRID_FILE: OptionSpec = OptionSpec::value("file", "read the grid from a file").short('f');
+const ROWS: PositionalSpec = PositionalSpec::many("rows", "rows of the grid, separated by spaces, / or ,");
+
+/// Result filters, which every command takes before or after its own
+/// arguments, and the order to sort its results by.
+pub const FILTER_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::value("len", "only show results with exactly this many letters"),
+    OptionSpec::value("min-len", "only show results with at least this many letters"),
+    OptionSpec::value("max-len", "only show results with at most this many letters"),
+    OptionSpec::value("contains", "only show results containing all these letters"),
+    OptionSpec::value("excludes", "only show results containing none of these letters"),
+    OptionSpec::value("starts", "only show results starting with these letters"),
+    OptionSpec::value("ends", "only show results ending with these letters"),
+    OptionSpec::value("match", "only show results matching this regex"),
+    OptionSpec::value("in-dict", "only show dictionary words with this tag, or any dictionary word for *"),
+    OptionSpec::value("sort", "order of results; by default alphabetical, except for commands that rank their results")
+        .choices(&["alpha", "length", "freq", "none"]),
+];
+
+/// What a query means when it doesn't start with a command's name.
+pub const REGEX_COMMAND: CommandSpec = command("<regex>", "words matching the given regex", &[],
+    &[PositionalSpec::one("regex", "regular expression")]);
+
+pub const COMMANDS: &[CommandSpec] = &[
+    command("anagram", "anagram of the letters", &[], LETTERS_ONLY),
+    aliased(&["td"], command("transpose-delete", "anagram of the letters after deleting n chars", N_OPTIONS, LETTERS_ONLY)),
+    command("delete", "words achievable by deleting n letters", N_OPTIONS, LETTERS_ONLY),
+    aliased(&["ta"], command("transpose-add", "words achievable after adding n chars", N_OPTIONS, LETTERS_ONLY)),
+    command("bank", "words using the same set of letters", &[], LETTERS_ONLY),
+    command("add", "words achievable by adding n letters", N_OPTIONS, LETTERS_ONLY),
+    command("change", "words achievable by changing n letters", N_OPTIONS, LETTERS_ONLY),
+    aliased(&["delete-front"], command("behead", "words achievable by deleting the first n letters", N_OPTIONS, LETTERS_ONLY)),
+    aliased(&["delete-back"], command("curtail", "words achievable by deleting the last n letters", N_OPTIONS, LETTERS_ONLY)),
+    aliased(&["delete-middle"], command("gut", "words achievable by deleting n adjacent letters from the middle", N_OPTIONS, LETTERS_ONLY)),
+    aliased(&["alternate"], command("delete-alternate", "words achievable by deleting every other letter", &[], LETTERS_ONLY)),
+    command("delete-at", "words achievable by deleting the letters at the given positions", &[
+        OptionSpec::value("positions", "comma-separated 1-based positions").short_only('p'),
+    ], LETTERS_ONLY),
+    aliased(&["add-front"], command("prefix", "words achievable by adding n letters to the front", N_OPTIONS, LETTERS_ONLY)),
+    aliased(&["add-back"], command("suffix", "words achievable by adding n letters to the back", N_OPTIONS, LETTERS_ONLY)),
+    aliased(&["add-middle"], command("infix", "words achievable by adding n adjacent letters to the middle", N_OPTIONS, LETTERS_ONLY)),
+    command("extract", "words spelled by selected letters of a phrase", &[
+        OptionSpec::value("rule", "which letters to select").short('r')
+            .choices(&["odd", "even", "nth", "first", "last", "at"]).default("first"),
+        OptionSpec::value("n", "select every nth letter (with --rule nth)").short_only('n').default("2"),
+        OptionSpec::value("offset", "1-based position of the first selected letter (with --rule nth)").default("1"),
+        OptionSpec::value("positions", "comma-separated 1-based positions (with --rule at)").short_only('p'),
+        OptionSpec::value("transform", "look up the extracted letters as-is, anagrammed or reversed").short('t')
+            .choices(&["plain", "anagram", "reverse"]).default("plain"),
+    ], &[PositionalSpec::one("phrase", "phrase to extract letters from")]),
+    command("acrostic", "words spelled by the first letters of each word in a phrase", &[
+        OptionSpec::flag("last", "use the last letter of each word instead"),
+        OptionSpec::value("target", "only find combinations spelling this word"),
+    ], &[PositionalSpec::many("phrase", "words of the phrase; list several candidates for a position as a/b/c")]),
+    command("sum", "words whose letter values sum to a given score", &[
+        OptionSpec::value("scheme", "letter values to use").short('s')
+            .choices(&["a1z26", "scrabble", "gematria"]).default("a1z26"),
+        OptionSpec::value("map-file", "file of `letter value` lines to use instead of a scheme"),
+        OptionSpec::value("eq", "exact score").conflicts(&["min", "max"]),
+        OptionSpec::value("min", "minimum score"),
+        OptionSpec::value("max", "maximum score"),
+        OptionSpec::flag("score", "show each word's score"),
+    ], &[PositionalSpec::one("regex", "only score words matching this regex")]),
+    aliased(&["t9"], command("keypad", "words spelled by telephone keypad digits, or the digits spelling a word", &[],
+        &[PositionalSpec::one("input", "digits to look up, or a word to spell as digits")])),
+    hyphen_values(command("decode", "decode a message; letters are separated by spaces or commas, words by /", &[
+        OptionSpec::flag("unspaced", "split a message whose codes run together into words").short('u'),
+        OptionSpec::value("max-words", "most words to split an unspaced message into").short('w').default("3"),
+    ], CODED)),
+    hyphen_values(command("encode", "encode a message", &[], CODED)),
+    command("segment", "split letters into dictionary words", &[
+        OptionSpec::flag("best", "only show the most probable segmentation").short('b'),
+        OptionSpec::value("max-words", "most words to split into").short('w'),
+        OptionSpec::value("min-word-len", "fewest letters in each word").default("1"),
+    ], LETTERS_ONLY),
+    command("grid", "words traceable through adjacent cells of a letter grid", &[
+        GRID_FILE,
+        OptionSpec::value("adjacency", "whether diagonal cells count as adjacent (8) or not (4)")
+            .choices(&["4", "8"]).default("8"),
+        OptionSpec::flag("reuse", "allow cells to be used more than once"),
+        OptionSpec::flag("wrap", "let words wrap around the grid's edges"),
+        OptionSpec::flag("straight", "only find words in straight lines, as in a word search"),
+        OptionSpec::value("min-word-len", "fewest letters in a word to look for").default("3"),
+    ], &[ROWS]),
+    command("wordsearch", "find a list of words in a word search grid, and the letters left over", &[
+        GRID_FILE,
+        OptionSpec::value("words", "comma-separated words to find; read from stdin if not given").short('w'),
+        OptionSpec::flag("leftover", "only print the leftover letters"),
+    ], &[ROWS]),
+    command("fill", "fill a crossword grid so every crossing agrees, listing each slot's candidates", &[
+        GRID_FILE,
+        OptionSpec::value("limit", "most fills to search for").default("100"),
+        OptionSpec::flag("fills", "print each complete fill as a grid, rather than each slot's words"),
+    ], &[PositionalSpec::many("rows", "rows of the grid, separated by spaces, / or ,; # is a block and . a blank")]),
+    command("wordle", "words consistent with wordle guesses and their feedback", &[
+        OptionSpec::value("length", "length of the answer").short('l').default("5"),
+        OptionSpec::flag("suggest", "rank next guesses by the information they'd give").short('s'),
+        OptionSpec::value("top", "how many guesses to suggest").default("10"),
+        OptionSpec::flag("any", "suggest from every word of the length, not just possible answers"),
+    ], &[PositionalSpec::many("guesses", "guesses as word:marks, with g for green, y for yellow and b for grey, e.g. crane:bygbb")]),
+    command("bee", "spelling bee words from a set of letters, always using the center one", &[
+        OptionSpec::value("center", "the letter every word must use; defaults to the first letter given").short('c'),
+        OptionSpec::flag("pangrams", "only show words using every letter"),
+        OptionSpec::value("min-word-len", "fewest letters in a word").default("4"),
+    ], LETTERS_ONLY),
+    command("letterbox", "shortest chains of words using every letter around a letter boxed box", &[
+        OptionSpec::value("max-words", "most words in a chain").short('w').default("3"),
+        OptionSpec::flag("words", "list every word that can be spelled around the box, rather than chains"),
+    ], &[PositionalSpec::many("sides", "letters on each side of the box, separated by spaces, / or ,")]),
+    command("subsequence", "words spelled by picking letters, in order, out of the given ones", &GAP_OPTIONS, LETTERS_ONLY),
+    command("supersequence", "words containing the given letters in order, though not necessarily together", &[
+        GAP_OPTIONS[0],
+        GAP_OPTIONS[1],
+        OptionSpec::flag("kangaroo", "leave out words containing the letters together").short('k'),
+    ], LETTERS_ONLY),
+];
+
+/// The command with this name or alias.
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+fn option_lines(options: &[OptionSpec]) -> Vec<String> {
+    options.iter()
+        .map(|option| {
+            let mut line = format!("  {:<24} {}", option.usage(), option.help);
+            if !option.choices.is_empty() {
+                line.push_str(&format!(" [{}]", option.choices.join(", ")));
+            }
+            if let Some(default) = option.default {
+                line.push_str(&format!(" (default: {})", default));
+            }
+            line
+        })
+        .collect()
+}
+
+/// Every command and filter, or one command's usage and options when a
+/// name is given.
+pub fn help(topic: Option<&str>) -> Result<String, String> {
+    let spec = match topic {
+        None => {
+            let mut lines = vec!["commands:".to_string()];
+            lines.extend(std::iter::once(&REGEX_COMMAND).chain(COMMANDS.iter())
+                .map(|spec| format!("  {:<24} {}", spec.name, spec.about)));
+            lines.push("filters, given before or after any command's arguments:".to_string());
+            lines.extend(option_lines(FILTER_OPTIONS));
+            lines.push("`help <command>` shows a command's options".to_string());
+            return Ok(lines.join("\n"));
+        },
+        Some("regex") => &REGEX_COMMAND,
+        Some(name) => find_command(name).ok_or_else(|| format!("no command named {}", name))?,
+    };
+    let mut lines = vec![format!("{}: {}", spec.name, spec.about), format!("usage: {}", spec.usage())];
+    if !spec.aliases.is_empty() {
+        lines.push(format!("also: {}", spec.aliases.join(", ")));
+    }
+    lines.extend(spec.positionals.iter().map(|positional| format!("  {:<24} {}", positional.name, positional.help)));
+    if !spec.options.is_empty() {
+        lines.push("options:".to_string());
+        lines.extend(option_lines(spec.options));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// The arguments given to a command, checked against its spec.
+#[derive(Debug)]
+pub struct Args {
+    spec: &'static CommandSpec,
+    values: Vec<(&'static str, Vec<String>)>,
+}
+
+impl Args {
+    fn given(&self, name: &str) -> Option<&[String]> {
+        self.values.iter().find(|(given, _)| *given == name).map(|(_, values)| &values[..])
+    }
+
+    /// The value given for an option or positional argument, or its default.
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        match self.given(name) {
+            Some(values) => values.first().map(String::as_str),
+            None => self.spec.find_option(|option| option.name == name).and_then(|option| option.default),
+        }
+    }
+
+    /// Every value given for a positional argument that takes several.
+    pub fn values_of(&self, name: &str) -> Option<impl Iterator<Item = &str>> {
+        self.given(name).map(|values| values.iter().map(String::as_str))
+    }
+
+    pub fn is_present(&self, name: &str) -> bool {
+        self.given(name).is_some()
+    }
+
+    fn add(&mut self, name: &'static str, value: String) {
+        match self.values.iter_mut().find(|(given, _)| *given == name) {
+            Some((_, values)) => values.push(value),
+            None => self.values.push((name, vec![value])),
+        }
+    }
+}
+
+/// A word of a command, with where it is in the query.
+struct Word<'a> {
+    text: &'a str,
+    quoted: bool,
+    span: Span,
+}
+
+fn check_choice(choices: &[&str], value: &str, what: &str, span: Span) -> Result<(), ParseError> {
+    if choices.is_empty() || choices.contains(&value) {
+        return Ok(());
+    }
+    Err(ParseError::new(format!("invalid {} '{}'; expected one of {}", what, value, choices.join(", ")), span))
+}
+
+/// Sorts a command's words into its options and positional arguments. Filter
+/// options may come before the command's name; the first other word is the
+/// name, or a regex when it isn't a command's.
+fn parse_words(words: &[Word]) -> Result<Args, ParseError> {
+    let mut args: Option<Args> = None;
+    let mut filters: Vec<(&'static str, String)> = Vec::new();
+    let mut positional = 0;
+    let mut only_positionals = false;
+    let mut idx = 0;
+    while idx < words.len() {
+        let word = &words[idx];
+        idx += 1;
+        let spec = args.as_ref().map_or(&REGEX_COMMAND, |args| args.spec);
+        if !word.quoted && !only_positionals && word.text == "--" {
+            only_positionals = true;
+            continue;
+        }
+        let is_option = !word.quoted && !only_positionals && word.text.len() > 1 && word.text.starts_with('-');
+        if is_option {
+            let (option, attached) = match word.text.strip_prefix("--") {
+                Some(long) => {
+                    let (name, attached) = match long.split_once('=') {
+                        Some((name, value)) => (name, Some(value)),
+                        None => (long, None),
+                    };
+                    (spec.find_option(|option| option.long && option.name == name), attached)
+                },
+                None => {
+                    let mut chars = word.text[1..].chars();
+                    let short = chars.next();
+                    let rest = chars.as_str();
+                    let attached = if rest.is_empty() { None } else { Some(rest.trim_start_matches('=')) };
+                    (spec.find_option(|option| option.short == short), attached)
+                },
+            };
+            // Before the command's name, only filters are known.
+            let option = option.filter(|option| args.is_some() || FILTER_OPTIONS.iter().any(|filter| filter.name == option.name));
+            match option {
+                Some(option) => {
+                    let value = match (option.takes_value, attached) {
+                        (false, Some(_)) => return Err(ParseError::new(format!("{} doesn't take a value", option.usage()), word.span)),
+                        (false, None) => String::new(),
+                        (true, Some(value)) => value.to_string(),
+                        (true, None) => {
+                            let value = words.get(idx).ok_or_else(|| ParseError::new(format!("{} needs a value", option.usage()), word.span))?;
+                            idx += 1;
+                            check_choice(option.choices, value.text, option.name, value.span)?;
+                            value.text.to_string()
+                        },
+                    };
+                    if attached.is_some() {
+                        check_choice(option.choices, &value, option.name, word.span)?;
+                    }
+                    let given_before = match &args {
+                        Some(args) => args.is_present(option.name),
+                        None => filters.iter().any(|(name, _)| *name == option.name),
+                    };
+                    if given_before {
+                        return Err(ParseError::new(format!("{} is given more than once", option.usage()), word.span));
+                    }
+                    let conflict = args.iter().flat_map(|args| args.values.iter().map(|(name, _)| *name)).find(|name| {
+                        option.conflicts.contains(name)
+                            || spec.find_option(|given| given.name == *name).is_some_and(|given| given.conflicts.contains(&option.name))
+                    });
+                    if let Some(conflict) = conflict {
+                        return Err(ParseError::new(format!("{} can't be used with --{}", option.usage(), conflict), word.span));
+                    }
+                    match &mut args {
+                        Some(args) => args.add(option.name, value),
+                        None => filters.push((option.name, value)),
+                    }
+                    continue;
+                },
+                None if args.as_ref().is_some_and(|args| args.spec.hyphen_values) => {},
+                None => {
+                    let message = match &args {
+                        Some(args) => format!("unknown option {} for {}", word.text, args.spec.name),
+                        None => format!("unknown option {}", word.text),
+                    };
+                    return Err(ParseError::new(message, word.span));
+                },
+            }
+        }
+        let args = match &mut args {
+            Some(args) => args,
+            None => {
+                let found = find_command(word.text).filter(|_| !word.quoted);
+                args = Some(Args { spec: found.unwrap_or(&REGEX_COMMAND), values: Vec::new() });
+                let args = args.as_mut().unwrap();
+                for (name, value) in filters.drain(..) {
+                    args.add(name, value);
+                }
+                if found.is_some() {
+                    continue;
+                }
+                args
+            },
+        };
+        let spec = args.spec;
+        let slot = spec.positionals.get(positional)
+            .ok_or_else(|| ParseError::new(format!("unexpected argument '{}' for {}", word.text, spec.name), word.span))?;
+        check_choice(slot.choices, word.text, slot.name, word.span)?;
+        args.add(slot.name, word.text.to_string());
+        if !slot.multiple {
+            positional += 1;
+        }
+    }
+    let args = args.ok_or_else(|| {
+        let end = words.last().map_or(0, |word| word.span.end);
+        ParseError::new("expected a command or regex", Span::new(end, end))
+    })?;
+    if let Some(missing) = args.spec.positionals.iter().find(|positional| positional.required && !args.is_present(positional.name)) {
+        let end = words.last().map_or(0, |word| word.span.end);
+        return Err(ParseError::new(format!("{} needs <{}>", args.spec.name, missing.name), Span::new(end, end)));
+    }
+    Ok(args)
+}
+
+/// Parses one command from its words: its name (or a regex), its arguments
+/// and any filters, which it's wrapped in along with its sort order.
+pub fn parse_tokens(tokens: &[Token]) -> Result<SiftCommand, ParseError> {
+    let words = tokens.iter()
+        .map(|token| match &token.kind {
+            TokenKind::Word { text, quoted } => Ok(Word { text, quoted: *quoted, span: token.span }),
+            _ => Err(ParseError::new("expected a word", token.span)),
+        })
+        .collect::<Result<Vec<Word>, ParseError>>()?;
+    let args = parse_words(&words)?;
+    let span = match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => first.span.to(last.span),
+        _ => Span::new(0, 0),
+    };
+    parse_command(&args).map_err(|err| ParseError::new(err.to_string(), span))
+}
+
+/// Parses a command from arguments the shell has already split.
+pub fn parse_args<S: AsRef<str>>(args: &[S]) -> Result<SiftCommand, ParseError> {
+    parse_tokens(&tokens_from_args(args).1)
+}
+
+fn get_regex(args: &Args) -> Result<RegexTemplate, SiftError> {
+    let pattern = args.value_of("regex").ok_or(SiftError::MissingLetters)?;
+    RegexTemplate::new(pattern)
+}
+
+fn get_letters(args: &Args) -> Result<String, SiftError> {
+    let letters = args.value_of("letters").ok_or(SiftError::MissingLetters)?;
+    template::validate(letters)?;
+    Ok(letters.to_string())
+}
+
+fn get_n(args: &Args) -> Result<usize, SiftError> {
+    let n_str = args.value_of("n").unwrap();
     str::parse::<usize>(n_str).map_err(|_| SiftError::InvalidNumber)
 }
 
-pub fn parse_command(matches: &ArgMatches) -> Result<SiftCommand, SiftError> {
-    match matches.subcommand() {
-        ("anagram", Some(sub_m)) => Ok(SiftCommand::Anagram(get_letters(sub_m)?)),
-        ("transpose-delete", Some(sub_m)) => Ok(SiftCommand::TransposeDelete(get_letters(sub_m)?, get_n(sub_m)?)),
-        ("delete", Some(sub_m)) => Ok(SiftCommand::Delete(get_letters(sub_m)?, get_n(sub_m)?)),
-        ("bank", Some(sub_m)) => Ok(SiftCommand::Bank(get_letters(sub_m)?)),
-        ("transpose-add", Some(sub_m)) => Ok(SiftCommand::TransposeAdd(get_letters(sub_m)?, get_n(sub_m)?)),
-        ("add", Some(sub_m)) => Ok(SiftCommand::Add(get_letters(sub_m)?, get_n(sub_m)?)),
-        ("change", Some(sub_m)) => Ok(SiftCommand::Change(get_letters(sub_m)?, get_n(sub_m)?)),
-        (_, Some(_)) => Err(SiftError::InvalidCommand),
-        (_, None) => Ok(SiftCommand::RegExp(get_regex(matches)?)),
+fn get_positions(args: &Args) -> Result<Vec<usize>, SiftError> {
+    let positions_str = args.value_of("positions").ok_or(SiftError::InvalidNumber)?;
+    positions_str.split(',')
+        .map(|pos| str::parse::<usize>(pos.trim()).map_err(|_| SiftError::InvalidNumber))
+        .collect()
+}
+
+fn get_usize(args: &Args, name: &str) -> Result<usize, SiftError> {
+    let value = args.value_of(name).ok_or(SiftError::InvalidNumber)?;
+    str::parse::<usize>(value).map_err(|_| SiftError::InvalidNumber)
+}
+
+fn get_selection(args: &Args) -> Result<Selection, SiftError> {
+    match args.value_of("rule") {
+        Some("odd") => Ok(Selection::Odd),
+        Some("even") => Ok(Selection::Even),
+        Some("nth") => {
+            let n = get_n(args)?;
+            let offset = get_usize(args, "offset")?;
+            if n == 0 || offset == 0 {
+                return Err(SiftError::InvalidNumber);
+            }
+            Ok(Selection::Nth(n, offset))
+        },
+        Some("last") => Ok(Selection::Last),
+        Some("at") => Ok(Selection::Positions(get_positions(args)?)),
+        _ => Ok(Selection::First),
+    }
+}
+
+fn get_transform(args: &Args) -> Transform {
+    match args.value_of("transform") {
+        Some("anagram") => Transform::Anagram,
+        Some("reverse") => Transform::Reverse,
+        _ => Transform::Plain,
+    }
+}
+
+fn get_scheme(args: &Args) -> Result<ScoreScheme, SiftError> {
+    if let Some(path) = args.value_of("map-file") {
+        let file = File::open(path).map_err(|err| {
+            eprintln!("{}", err);
+            SiftError::FileIOError
+        })?;
+        return Ok(ScoreScheme::Custom(read_mapping(file)?));
+    }
+    match args.value_of("scheme") {
+        Some("scrabble") => Ok(ScoreScheme::Scrabble),
+        Some("gematria") => Ok(ScoreScheme::Gematria),
+        _ => Ok(ScoreScheme::A1Z26),
+    }
+}
+
+fn get_score_range(args: &Args) -> Result<RangeInclusive<u32>, SiftError> {
+    let get_score = |name| args.value_of(name)
+        .map(|value| str::parse::<u32>(value).map_err(|_| SiftError::InvalidNumber))
+        .transpose();
+    if let Some(score) = get_score("eq")? {
+        return Ok(score..=score);
+    }
+    let min = get_score("min")?.unwrap_or(0);
+    let max = get_score("max")?.unwrap_or(u32::MAX);
+    Ok(min..=max)
+}
+
+fn get_encoding(args: &Args) -> Encoding {
+    match args.value_of("encoding") {
+        Some("braille") => Encoding::Braille,
+        Some("semaphore") => Encoding::Semaphore,
+        Some("binary") => Encoding::Binary,
+        Some("nato") => Encoding::Nato,
+        _ => Encoding::Morse,
+    }
+}
+
+fn get_message(args: &Args) -> Result<String, SiftError> {
+    let message = args.values_of("message")
+        .map(|words| words.collect::<Vec<_>>().join(" "))
+        .ok_or(SiftError::MissingLetters)?;
+    template::validate(&message)?;
+    Ok(message)
+}
+
+fn read_file(path: &str) -> Result<String, SiftError> {
+    std::fs::read_to_string(path).map_err(|err| {
+        eprintln!("{}", err);
+        SiftError::FileIOError
+    })
+}
+
+fn get_grid(args: &Args) -> Result<Grid, SiftError> {
+    match (args.value_of("file"), args.values_of("rows")) {
+        (Some(path), _) => Grid::parse(&read_file(path)?),
+        (None, Some(rows)) => Grid::parse(&rows.collect::<Vec<_>>().join(" ")),
+        (None, None) => Err(SiftError::MissingLetters),
+    }
+}
+
+fn get_crossword(args: &Args) -> Result<Crossword, SiftError> {
+    match (args.value_of("file"), args.values_of("rows")) {
+        (Some(path), _) => Crossword::parse(&read_file(path)?),
+        (None, Some(rows)) => Crossword::parse(&rows.collect::<Vec<_>>().join(" ")),
+        (None, None) => Err(SiftError::MissingLetters),
+    }
+}
+
+fn get_gaps(args: &Args) -> Result<Gaps, SiftError> {
+    let max = match args.value_of("max-gap") {
+        Some(_) => Some(get_usize(args, "max-gap")?),
+        None => None,
+    };
+    Ok(Gaps { min: get_usize(args, "min-gap")?, max })
+}
+
+fn get_grid_options(args: &Args) -> Result<GridOptions, SiftError> {
+    Ok(GridOptions {
+        diagonals: args.value_of("adjacency") != Some("4"),
+        reuse: args.is_present("reuse"),
+        wrap: args.is_present("wrap"),
+        straight: args.is_present("straight"),
+        min_len: get_usize(args, "min-word-len")?,
+    })
+}
+
+fn get_filter(args: &Args) -> Result<ResultFilter, SiftError> {
+    let get_len = |name: &str| args.value_of(name).map(|_| get_usize(args, name)).transpose();
+    let get_string = |name: &str| args.value_of(name).map(str::to_string);
+    let pattern = match args.value_of("match") {
+        Some(pattern) => Some(whole_word_regex(pattern).map_err(|_| SiftError::InvalidRegExp)?),
+        None => None,
+    };
+    Ok(ResultFilter {
+        len: get_len("len")?,
+        min_len: get_len("min-len")?,
+        max_len: get_len("max-len")?,
+        contains: get_string("contains"),
+        excludes: get_string("excludes"),
+        starts: get_string("starts"),
+        ends: get_string("ends"),
+        pattern,
+        in_dict: get_string("in-dict"),
+    })
+}
+
+/// Parses a command, wrapping it in any result filters given and in the
+/// order to sort its results by.
+pub fn parse_command(args: &Args) -> Result<SiftCommand, SiftError> {
+    let mut command = parse_unfiltered_command(args)?;
+    let filter = get_filter(args)?;
+    if !filter.is_empty() {
+        command = SiftCommand::Filter(Box::new(command), filter);
+    }
+    match args.value_of("sort") {
+        Some(name) => {
+            let order = SortOrder::from_name(name).ok_or(SiftError::InvalidCommand)?;
+            Ok(SiftCommand::Sort(Box::new(command), order))
+        },
+        None => Ok(command),
+    }
+}
+
+fn parse_unfiltered_command(args: &Args) -> Result<SiftCommand, SiftError> {
+    match args.spec.name {
+        "anagram" => Ok(SiftCommand::Anagram(get_letters(args)?)),
+        "transpose-delete" => Ok(SiftCommand::TransposeDelete(get_letters(args)?, get_n(args)?)),
+        "delete" => Ok(SiftCommand::Delete(get_letters(args)?, get_n(args)?)),
+        "bank" => Ok(SiftCommand::Bank(get_letters(args)?)),
+        "transpose-add" => Ok(SiftCommand::TransposeAdd(get_letters(args)?, get_n(args)?)),
+        "add" => Ok(SiftCommand::Add(get_letters(args)?, get_n(args)?)),
+        "change" => Ok(SiftCommand::Change(get_letters(args)?, get_n(args)?)),
+        "behead" => Ok(SiftCommand::Behead(get_letters(args)?, get_n(args)?)),
+        "curtail" => Ok(SiftCommand::Curtail(get_letters(args)?, get_n(args)?)),
+        "gut" => Ok(SiftCommand::Gut(get_letters(args)?, get_n(args)?)),
+        "delete-alternate" => Ok(SiftCommand::DeleteAlternate(get_letters(args)?)),
+        "delete-at" => Ok(SiftCommand::DeleteAt(get_letters(args)?, get_positions(args)?)),
+        "prefix" => Ok(SiftCommand::Prefix(get_letters(args)?, get_n(args)?)),
+        "suffix" => Ok(SiftCommand::Suffix(get_letters(args)?, get_n(args)?)),
+        "infix" => Ok(SiftCommand::Infix(get_letters(args)?, get_n(args)?)),
+        "extract" => {
+            let phrase = args.value_of("phrase").ok_or(SiftError::MissingLetters)?;
+            template::validate(phrase)?;
+            Ok(SiftCommand::Extract(phrase.to_string(), get_selection(args)?, get_transform(args)))
+        },
+        "acrostic" => {
+            let phrase = args.values_of("phrase").map(|words| words.collect::<Vec<_>>().join(" ")).unwrap_or_default();
+            template::validate(&phrase)?;
+            let target = args.value_of("target").map(String::from);
+            Ok(SiftCommand::Acrostic(phrase, args.is_present("last"), target))
+        },
+        "sum" => {
+            let regex = match args.value_of("regex") {
+                Some(_) => get_regex(args)?,
+                None => RegexTemplate::new(".*").unwrap(),
+            };
+            Ok(SiftCommand::Sum(regex, get_scheme(args)?, get_score_range(args)?, args.is_present("score")))
+        },
+        "keypad" => {
+            let input = args.value_of("input").ok_or(SiftError::MissingLetters)?;
+            template::validate(input)?;
+            Ok(SiftCommand::Keypad(input.to_string()))
+        },
+        "decode" => {
+            let max_words = get_usize(args, "max-words")?;
+            Ok(SiftCommand::Decode(get_encoding(args), get_message(args)?, args.is_present("unspaced"), max_words))
+        },
+        "encode" => Ok(SiftCommand::Encode(get_encoding(args), get_message(args)?)),
+        "segment" => {
+            let max_words = match args.value_of("max-words") {
+                Some(_) => get_usize(args, "max-words")?,
+                None => 0,
+            };
+            let min_len = get_usize(args, "min-word-len")?;
+            Ok(SiftCommand::Segment(get_letters(args)?, max_words, min_len, args.is_present("best")))
+        },
+        "grid" => Ok(SiftCommand::WordGrid(get_grid(args)?, get_grid_options(args)?)),
+        "wordsearch" => {
+            let words: Vec<String> = args.value_of("words")
+                .map(|words| words.split(',').map(|word| word.trim().to_string()).filter(|word| !word.is_empty()).collect())
+                .unwrap_or_default();
+            words.iter().try_for_each(|word| template::validate(word))?;
+            Ok(SiftCommand::WordSearch(get_grid(args)?, words, args.is_present("leftover")))
+        },
+        "fill" => {
+            Ok(SiftCommand::Fill(get_crossword(args)?, get_usize(args, "limit")?, args.is_present("fills")))
+        },
+        "wordle" => {
+            let length = get_usize(args, "length")?;
+            let guesses = args.values_of("guesses")
+                .map(|guesses| guesses.map(Guess::parse).collect::<Result<Vec<Guess>, SiftError>>())
+                .transpose()?
+                .unwrap_or_default();
+            if guesses.iter().any(|guess| guess.word.len() != length) {
+                return Err(SiftError::InvalidGuess);
+            }
+            let top = if args.is_present("suggest") {
+                Some(get_usize(args, "top")?)
+            } else {
+                None
+            };
+            Ok(SiftCommand::Wordle(length, guesses, top, args.is_present("any")))
+        },
+        "bee" => {
+            let letters = get_letters(args)?.to_ascii_lowercase();
+            let center = match args.value_of("center") {
+                Some(center) => center.chars().next(),
+                None => letters.chars().next(),
+            };
+            let center = center
+                .map(|center| center.to_ascii_lowercase())
+                .filter(|center| center.is_ascii_lowercase() && (letters.contains(*center) || letters.contains('%')))
+                .ok_or(SiftError::InvalidCharacters)?;
+            Ok(SiftCommand::SpellingBee(letters, center, get_usize(args, "min-word-len")?, args.is_present("pangrams")))
+        },
+        "letterbox" => {
+            let sides = args.values_of("sides").ok_or(SiftError::MissingLetters)?;
+            let letter_box = LetterBox::parse(&sides.collect::<Vec<_>>().join(" "))?;
+            Ok(SiftCommand::LetterBoxed(letter_box, get_usize(args, "max-words")?, args.is_present("words")))
+        },
+        "subsequence" => Ok(SiftCommand::Subsequence(get_letters(args)?, get_gaps(args)?)),
+        "supersequence" => {
+            Ok(SiftCommand::Supersequence(get_letters(args)?, get_gaps(args)?, args.is_present("kangaroo")))
+        },
+        _ => Ok(SiftCommand::RegExp(get_regex(args)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn parse(query: &str) -> Result<SiftCommand, ParseError> {
+        parse_tokens(&tokenize(query).unwrap())
+    }
+
+    fn error_at(query: &str) -> (String, Span) {
+        let err = parse(query).unwrap_err();
+        (err.message, err.span)
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut names: Vec<&str> = COMMANDS.iter().flat_map(|spec| std::iter::once(spec.name).chain(spec.aliases.iter().cloned())).collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count, "a command name is registered twice");
+        assert!(help(Some("extract")).unwrap().contains("--rule <rule>"));
+        assert!(help(None).unwrap().contains("supersequence"));
+        assert!(help(Some("bogus")).is_err());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert!(matches!(parse_args(&["delete", "-n", "2", "horse"]), Ok(SiftCommand::Delete(_, 2))));
+        assert!(matches!(parse("td -n2 horse"), Ok(SiftCommand::TransposeDelete(_, 2))));
+        assert!(matches!(parse("--len=4 h.se"), Ok(SiftCommand::Filter(_, _))));
+        assert!(matches!(parse("extract 'hungry owls'"), Ok(SiftCommand::Extract(phrase, _, _)) if phrase == "hungry owls"));
+        assert!(matches!(parse("decode morse -.. --- --. -u"), Ok(SiftCommand::Decode(_, message, true, 3)) if message == "-.. --- --."));
+        assert!(matches!(parse("'anagram'"), Ok(SiftCommand::RegExp(_))));
+        assert!(matches!(parse("-- -n"), Ok(SiftCommand::RegExp(_))));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(error_at("anagram horse --bogus"), ("unknown option --bogus for anagram".to_string(), Span::new(14, 21)));
+        assert_eq!(error_at("-n 2 delete horse"), ("unknown option -n".to_string(), Span::new(0, 2)));
+        assert_eq!(error_at("anagram horse shoe"), ("unexpected argument 'shoe' for anagram".to_string(), Span::new(14, 18)));
+        assert_eq!(error_at("delete horse -n"), ("-n <n> needs a value".to_string(), Span::new(13, 15)));
+        assert_eq!(error_at("extract --rule third x").1, Span::new(15, 20));
+        assert_eq!(error_at("anagram --len 3 --len 4 x").1, Span::new(16, 21));
+        assert_eq!(error_at("sum --max 3 --eq 2").1, Span::new(12, 16));
+        assert_eq!(error_at("decode").0, "decode needs <encoding>");
+        assert_eq!(error_at("delete -n x horse"), ("invalid number".to_string(), Span::new(0, 17)));
+    }
+
+    #[test]
+    fn test_sort_order() {
+        assert!(matches!(parse_args(&["anagram", "hose"]), Ok(SiftCommand::Anagram(_))));
+        assert!(matches!(parse_args(&["--sort", "alpha", "anagram", "hose"]), Ok(SiftCommand::Sort(_, SortOrder::Alpha))));
+        assert!(matches!(parse_args(&["--sort", "freq", "anagram", "hose"]), Ok(SiftCommand::Sort(_, SortOrder::Freq))));
+        assert!(matches!(parse_args(&["anagram", "hose", "--sort", "none"]), Ok(SiftCommand::Sort(_, SortOrder::Unsorted))));
+        assert!(matches!(parse_args(&["segment", "hoseshoe", "--best"]), Ok(SiftCommand::Segment(_, _, _, true))));
+        assert!(parse_args(&["--sort", "bogus", "anagram", "hose"]).is_err());
     }
 }
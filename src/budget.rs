@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+use wasm_bindgen::prelude::*;
+
+/// Limits on how much work one query may do. Commands that try every way of
+/// adding, deleting or changing n letters look up `C(len + n, n)` patterns,
+/// so a large n can otherwise run for ages. No limits are set by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Budget {
+    /// Most patterns any one command may look up.
+    pub max_patterns: Option<usize>,
+    /// Most results a query may give.
+    pub max_results: Option<usize>,
+    /// How long a query may run.
+    pub timeout: Option<Duration>,
+}
+
+impl Budget {
+    /// Starts spending the budget on a query.
+    pub fn start(self) -> Work {
+        Work { budget: self, started: now_ms(), cut_short: AtomicBool::new(false) }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Date, js_name = now)]
+    fn date_now() -> f64;
+}
+
+/// `Instant` panics in the browser, so time comes from JavaScript instead.
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+fn now_ms() -> f64 {
+    date_now()
+}
+
+/// There's no clock to read in wasm without the bindings, so timeouts never
+/// pass.
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+fn now_ms() -> f64 {
+    0.0
+}
+
+/// A budget being spent by a running query. Whatever runs out of it stops
+/// early with the results it has, and the query's results are marked partial.
+#[derive(Debug)]
+pub struct Work {
+    budget: Budget,
+    started: f64,
+    cut_short: AtomicBool,
+}
+
+impl Work {
+    pub fn unlimited() -> Work {
+        Budget::default().start()
+    }
+
+    fn stop(&self) -> bool {
+        self.cut_short.store(true, Ordering::Relaxed);
+        false
+    }
+
+    /// Whether there's time left to keep working.
+    pub fn has_time(&self) -> bool {
+        match self.budget.timeout {
+            Some(timeout) if now_ms() - self.started >= timeout.as_secs_f64() * 1000.0 => self.stop(),
+            _ => true,
+        }
+    }
+
+    /// Whether a command that has made `made` patterns may make another.
+    pub fn allows_pattern(&self, made: usize) -> bool {
+        match self.budget.max_patterns {
+            Some(max) if made >= max => self.stop(),
+            _ => self.has_time(),
+        }
+    }
+
+    /// Whether a command that has found `found` results may add another.
+    pub fn allows_result(&self, found: usize) -> bool {
+        match self.budget.max_results {
+            Some(max) if found >= max => self.stop(),
+            _ => self.has_time(),
+        }
+    }
+
+    /// The items `keep` accepts, until time runs out or there are as many as
+    /// a query may give.
+    pub fn collect_matching<T, I, F>(&self, items: I, keep: F) -> Vec<T>
+        where I: IntoIterator<Item = T>, F: Fn(&T) -> bool
+    {
+        let mut results = Vec::new();
+        for item in items {
+            if !self.has_time() {
+                break;
+            }
+            if keep(&item) {
+                if !self.allows_result(results.len()) {
+                    break;
+                }
+                results.push(item);
+            }
+        }
+        results
+    }
+
+    /// Runs `f` with the same deadline and pattern limit but no cap on
+    /// results, for results that are taken away from others: capping those
+    /// would let through results that should have been removed.
+    pub fn uncapped<T, F: FnOnce(&Work) -> T>(&self, f: F) -> T {
+        let budget = Budget { max_results: None, ..self.budget };
+        let work = Work { budget, started: self.started, cut_short: AtomicBool::new(false) };
+        let result = f(&work);
+        if work.is_partial() {
+            self.stop();
+        }
+        result
+    }
+
+    /// Drops any results past the most a query may give.
+    pub fn limit_results<T>(&self, results: &mut Vec<T>) {
+        if let Some(max) = self.budget.max_results {
+            if results.len() > max {
+                results.truncate(max);
+                self.stop();
+            }
+        }
+    }
+
+    /// Whether any limit was reached, so the results may be missing some.
+    pub fn is_partial(&self) -> bool {
+        self.cut_short.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let work = Work::unlimited();
+        assert!(work.allows_pattern(usize::MAX - 1));
+        let mut results = vec![1; 100];
+        work.limit_results(&mut results);
+        assert_eq!(results.len(), 100);
+        assert!(!work.is_partial());
+
+        let work = Budget { max_patterns: Some(10), max_results: Some(3), timeout: None }.start();
+        assert!(work.allows_pattern(9));
+        assert!(!work.is_partial());
+        assert!(!work.allows_pattern(10));
+        assert!(work.is_partial());
+        let mut results = vec![1, 2, 3, 4];
+        work.limit_results(&mut results);
+        assert_eq!(results, vec![1, 2, 3]);
+
+        let work = Budget { timeout: Some(Duration::from_secs(0)), ..Budget::default() }.start();
+        assert!(!work.has_time());
+        assert!(work.is_partial());
+    }
+
+    #[test]
+    fn test_results() {
+        let work = Budget { max_results: Some(2), ..Budget::default() }.start();
+        assert_eq!(work.collect_matching(1..10, |n| n % 2 == 0), vec![2, 4]);
+        assert!(work.is_partial());
+        let work = Budget { max_results: Some(2), ..Budget::default() }.start();
+        assert_eq!(work.collect_matching(1..5, |&n| n > 2), vec![3, 4]);
+        assert!(!work.is_partial());
+        assert_eq!(work.uncapped(|work| work.collect_matching(1..5, |_| true)), vec![1, 2, 3, 4]);
+        assert!(!work.is_partial());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::budget::Work;
+use crate::error::SiftError;
+use std::collections::HashSet;
+use std::fmt;
+
+const BLOCK: char = '#';
+const BLANK: char = '.';
+
+/// A run of two or more open cells, across or down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot {
+    pub number: usize,
+    pub across: bool,
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.number, if self.across { 'a' } else { 'd' })
+    }
+}
+
+/// A crossword grid of blocks, blank cells and given letters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crossword {
+    rows: usize,
+    cols: usize,
+    cells: Vec<char>,
+}
+
+/// Where two slots share a cell: the first slot's index and letter position,
+/// then the second's.
+type Crossing = (usize, usize, usize, usize);
+
+impl Crossword {
+    /// Parses a grid from rows separated by whitespace, '/' or ','. Blocks
+    /// are written '#', and blank cells '.', '_' or '?'.
+    pub fn parse(text: &str) -> Result<Crossword, SiftError> {
+        let rows: Vec<Vec<char>> = text.split(|c: char| c.is_whitespace() || c == '/' || c == ',')
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars()
+                .map(|c| match c {
+                    '#' => Some(BLOCK),
+                    '.' | '_' | '?' => Some(BLANK),
+                    c if c.is_ascii_alphabetic() => Some(c.to_ascii_lowercase()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<char>>>())
+            .collect::<Option<_>>()
+            .ok_or(SiftError::InvalidGrid)?;
+        let cols = rows.first().map(|row| row.len()).ok_or(SiftError::InvalidGrid)?;
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(SiftError::InvalidGrid);
+        }
+        Ok(Crossword { rows: rows.len(), cols, cells: rows.concat() })
+    }
+
+    fn get(&self, (row, col): (usize, usize)) -> char {
+        self.cells[row * self.cols + col]
+    }
+
+    fn is_open(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols && self.get((row, col)) != BLOCK
+    }
+
+    /// The slots in clue order, numbered the way a published crossword is.
+    pub fn slots(&self) -> Vec<Slot> {
+        let mut slots = Vec::new();
+        let mut number = 0;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if !self.is_open(row, col) {
+                    continue;
+                }
+                let starts_across = (col == 0 || !self.is_open(row, col - 1)) && self.is_open(row, col + 1);
+                let starts_down = (row == 0 || !self.is_open(row - 1, col)) && self.is_open(row + 1, col);
+                if !starts_across && !starts_down {
+                    continue;
+                }
+                number += 1;
+                if starts_across {
+                    let cells = (col..self.cols).take_while(|&c| self.is_open(row, c)).map(|c| (row, c)).collect();
+                    slots.push(Slot { number, across: true, cells });
+                }
+                if starts_down {
+                    let cells = (row..self.rows).take_while(|&r| self.is_open(r, col)).map(|r| (r, col)).collect();
+                    slots.push(Slot { number, across: false, cells });
+                }
+            }
+        }
+        slots
+    }
+
+    /// The slot as a trie pattern, with '.' for each blank cell.
+    pub fn pattern(&self, slot: &Slot) -> String {
+        slot.cells.iter().map(|&cell| self.get(cell)).collect()
+    }
+
+    /// The grid with each slot's word written in, as rows separated by '/'.
+    pub fn render(&self, slots: &[Slot], words: &[&str]) -> String {
+        let mut cells = self.cells.clone();
+        for (slot, word) in slots.iter().zip(words) {
+            for (&(row, col), letter) in slot.cells.iter().zip(word.chars()) {
+                cells[row * self.cols + col] = letter;
+            }
+        }
+        cells.chunks(self.cols)
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+fn crossings(slots: &[Slot]) -> Vec<Crossing> {
+    let mut crossings = Vec::new();
+    for (a, slot_a) in slots.iter().enumerate() {
+        for (b, slot_b) in slots.iter().enumerate().skip(a + 1) {
+            for (i, cell) in slot_a.cells.iter().enumerate() {
+                if let Some(j) = slot_b.cells.iter().position(|other| other == cell) {
+                    crossings.push((a, i, b, j));
+                }
+            }
+        }
+    }
+    crossings
+}
+
+fn letter_at(word: &str, pos: usize) -> Option<char> {
+    word.chars().nth(pos)
+}
+
+/// Drops candidates from the first slot that no candidate of the second can
+/// cross, returning whether anything was dropped.
+fn revise(candidates: &mut [Vec<&str>], (a, i, b, j): Crossing) -> bool {
+    let allowed: HashSet<char> = candidates[b].iter().filter_map(|word| letter_at(word, j)).collect();
+    let before = candidates[a].len();
+    candidates[a].retain(|word| letter_at(word, i).is_some_and(|letter| allowed.contains(&letter)));
+    candidates[a].len() != before
+}
+
+/// Narrows every slot's candidates until each remaining word crosses some
+/// candidate in every slot it meets. Returns false if a slot runs dry.
+fn propagate(candidates: &mut [Vec<&str>], crossings: &[Crossing]) -> bool {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(a, i, b, j) in crossings {
+            changed |= revise(candidates, (a, i, b, j));
+            changed |= revise(candidates, (b, j, a, i));
+        }
+        if candidates.iter().any(|words| words.is_empty()) {
+            return false;
+        }
+    }
+    true
+}
+
+fn search<'a>(mut candidates: Vec<Vec<&'a str>>, crossings: &[Crossing], limit: usize, fills: &mut Vec<Vec<&'a str>>,
+              work: &Work) {
+    if fills.len() >= limit || !work.has_time() || !propagate(&mut candidates, crossings) {
+        return;
+    }
+    let open = candidates.iter()
+        .enumerate()
+        .filter(|(_, words)| words.len() > 1)
+        .min_by_key(|(_, words)| words.len())
+        .map(|(slot, _)| slot);
+    let slot = match open {
+        Some(slot) => slot,
+        None => {
+            let fill: Vec<&str> = candidates.into_iter().map(|words| words[0]).collect();
+            let distinct: HashSet<&&str> = fill.iter().collect();
+            if distinct.len() == fill.len() {
+                fills.push(fill);
+            }
+            return;
+        },
+    };
+    for &word in &candidates[slot] {
+        let mut next = candidates.clone();
+        for (other, words) in next.iter_mut().enumerate() {
+            if other == slot {
+                *words = vec![word];
+            } else {
+                words.retain(|&candidate| candidate != word);
+            }
+        }
+        search(next, crossings, limit, fills, work);
+        if fills.len() >= limit || !work.has_time() {
+            return;
+        }
+    }
+}
+
+/// What a search for fills found.
+#[derive(Debug, PartialEq)]
+pub struct Solution<'a> {
+    /// Complete fills, each giving one word per slot.
+    pub fills: Vec<Vec<&'a str>>,
+    /// The words each slot takes across every fill, when the search found
+    /// them all; otherwise the candidates left once narrowed against every
+    /// crossing.
+    pub candidates: Vec<Vec<&'a str>>,
+    /// Whether the search stopped at its limit, so there may be more fills.
+    pub limited: bool,
+}
+
+/// Finds up to `limit` fills of the slots using their candidate words, each
+/// fill giving one word per slot. No word is used twice in a fill.
+pub fn solve<'a>(slots: &[Slot], candidates: Vec<Vec<&'a str>>, limit: usize) -> Solution<'a> {
+    solve_within(slots, candidates, limit, &Work::unlimited())
+}
+
+/// Like `solve`, giving the fills found before the work runs out of time.
+pub fn solve_within<'a>(slots: &[Slot], mut candidates: Vec<Vec<&'a str>>, limit: usize, work: &Work) -> Solution<'a> {
+    let crossings = crossings(slots);
+    let mut fills = Vec::new();
+    if limit > 0 {
+        search(candidates.clone(), &crossings, limit, &mut fills, work);
+    }
+    let limited = fills.len() >= limit;
+    if limited || fills.is_empty() || !work.has_time() {
+        propagate(&mut candidates, &crossings);
+    } else {
+        for (idx, words) in candidates.iter_mut().enumerate() {
+            *words = fills.iter().map(|fill| fill[idx]).collect();
+            words.sort_unstable();
+            words.dedup();
+        }
+    }
+    Solution { fills, candidates, limited }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let crossword = Crossword::parse("h.. ._# ?#.").unwrap();
+        assert_eq!(crossword.render(&[], &[]), "h../..#/.#.");
+        assert!(Crossword::parse("ab. a").is_err());
+        assert!(Crossword::parse("a*b").is_err());
+    }
+
+    #[test]
+    fn test_slots() {
+        let crossword = Crossword::parse("h.. ..# .#.").unwrap();
+        let slots = crossword.slots();
+        let labels: Vec<String> = slots.iter().map(|slot| slot.to_string()).collect();
+        assert_eq!(labels, vec!["1a", "1d", "2d", "3a"]);
+        assert_eq!(crossword.pattern(&slots[0]), "h..");
+        assert_eq!(slots[3].cells, vec![(1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_solve() {
+        let crossword = Crossword::parse("ab. ...").unwrap();
+        let slots = crossword.slots();
+        let candidates = vec![
+            vec!["abc", "abd"],
+            vec!["ax", "ay"],
+            vec!["by", "bz"],
+            vec!["cz", "dq"],
+            vec!["xyz", "yzq", "xzq"],
+        ];
+        let solution = solve(&slots, candidates.clone(), 10);
+        assert!(!solution.limited);
+        assert_eq!(solution.candidates[1], vec!["ax", "ay"]);
+        let mut fills = solution.fills;
+        fills.sort();
+        assert_eq!(fills, vec![
+            vec!["abc", "ax", "by", "cz", "xyz"],
+            vec!["abd", "ax", "bz", "dq", "xzq"],
+            vec!["abd", "ay", "bz", "dq", "yzq"],
+        ]);
+        assert_eq!(crossword.render(&slots, &fills[0]), "abc/xyz");
+        let solution = solve(&slots, candidates, 1);
+        assert_eq!(solution.fills.len(), 1);
+        assert!(solution.limited);
+        assert_eq!(solution.candidates[4], vec!["xyz", "yzq", "xzq"]);
+    }
+
+    #[test]
+    fn test_solve_distinct_words() {
+        let crossword = Crossword::parse("ab ..").unwrap();
+        let slots = crossword.slots();
+        let candidates = vec![vec!["ab"], vec!["aa"], vec!["ba"], vec!["aa"]];
+        let solution = solve(&slots, candidates.clone(), 10);
+        assert!(solution.fills.is_empty());
+        assert_eq!(solution.candidates, candidates);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::trie::Trie;
-use crate::argparse::SiftError;
+use crate::trie::{Trie, TrieNode};
+use crate::error::SiftError;
+use crate::score::ScoreScheme;
 
 use flate2::write::DeflateEncoder;
 use flate2::read::DeflateDecoder;
@@ -7,15 +8,32 @@ use flate2::Compression;
 use std::iter::FromIterator;
 use std::io::prelude::*;
 use std::io::{BufReader, BufWriter};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use bincode::{serialize_into, deserialize_from};
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Dictionary {
     words: Vec<String>,
+    /// How common each word is, from `word count` lines in the word list. Words
+    /// listed without a count have a frequency of 0.
+    frequencies: Vec<u64>,
+    /// Indices of the words carrying each tag, from `#tag` fields after the
+    /// word (and count) in the word list.
+    tags: HashMap<String, HashSet<usize>>,
     words_trie: Trie,
     anagrams: Trie,
+    /// Letter sums under the built-in scoring schemes, computed on load
+    /// rather than stored in the cache.
+    #[serde(skip)]
+    sums: Vec<LetterSums>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LetterSums {
+    a1z26: u32,
+    scrabble: u32,
+    gematria: u32,
 }
 
 pub fn sort_letters(word: &str) -> String {
@@ -26,10 +44,20 @@ pub fn sort_letters(word: &str) -> String {
 
 impl Dictionary {
     pub fn new(unfiltered_words: Vec<String>) -> Dictionary {
-        let words = Dictionary::filter_and_normalize(unfiltered_words);
+        let mut words = Vec::new();
+        let mut frequencies = Vec::new();
+        let mut tags: HashMap<String, HashSet<usize>> = HashMap::new();
+        for (idx, (word, frequency, word_tags)) in Dictionary::filter_and_normalize(unfiltered_words).into_iter().enumerate() {
+            words.push(word);
+            frequencies.push(frequency);
+            for tag in word_tags {
+                tags.entry(tag).or_default().insert(idx);
+            }
+        }
         let words_trie = Dictionary::new_word_trie(&words);
         let anagrams = Dictionary::new_anagram_trie(&words);
-        Dictionary { words, words_trie, anagrams }
+        let sums = Dictionary::letter_sums(&words);
+        Dictionary { words, frequencies, tags, words_trie, anagrams, sums }
     }
 
     pub fn write_cache<W>(&self, writer: W) -> Result<(), SiftError> where W: Write {
@@ -43,16 +71,20 @@ impl Dictionary {
 
     pub fn new_from_cache<R>(data: R) -> Result<Dictionary, SiftError> where R: Read {
         let bufread = BufReader::new(data);
-        deserialize_from(DeflateDecoder::new(bufread))
+        let mut dict: Dictionary = deserialize_from(DeflateDecoder::new(bufread))
             .map_err(|err| {
                 eprintln!("{}", err);
                 SiftError::DeserializationError
-            })
+            })?;
+        dict.sums = Dictionary::letter_sums(&dict.words);
+        Ok(dict)
     }
 
     pub fn new_from_words<R>(data: R) -> Dictionary where R: Read {
-        let words = BufReader::new(data).lines()
-            .flat_map(|line| line)
+        let words = BufReader::new(data).split(b'\n')
+            .map_while(Result::ok)
+            .filter_map(|line| String::from_utf8(line).ok())
+            .map(|line| line.trim_end_matches('\r').to_string())
             .collect();
         Dictionary::new(words)
     }
@@ -61,6 +93,51 @@ impl Dictionary {
         HashSet::from_iter(self.words.iter().map(|s| s.as_ref()))
     }
 
+    pub fn word(&self, idx: usize) -> &str {
+        &self.words[idx]
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn frequency(&self, idx: usize) -> u64 {
+        self.frequencies[idx]
+    }
+
+    pub fn total_frequency(&self) -> u64 {
+        self.frequencies.iter().sum()
+    }
+
+    /// How common a word is, or 0 if it isn't in the dictionary.
+    pub fn word_frequency(&self, word: &str) -> u64 {
+        if word.is_empty() || !word.chars().all(char::is_alphabetic) {
+            return 0;
+        }
+        self.words_trie.lookup(&word.to_lowercase()).iter()
+            .map(|&idx| self.frequencies[idx])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether the word is in the dictionary with the given tag. The tag "*"
+    /// matches any dictionary word.
+    pub fn has_tag(&self, word: &str, tag: &str) -> bool {
+        if word.is_empty() || !word.chars().all(char::is_alphabetic) {
+            return false;
+        }
+        let indices = self.words_trie.lookup(&word.to_lowercase());
+        match tag {
+            "*" => !indices.is_empty(),
+            _ => self.tags.get(tag).is_some_and(|tagged| indices.iter().any(|idx| tagged.contains(idx))),
+        }
+    }
+
+    /// Root of the word trie, for letter-by-letter walks.
+    pub fn words_root(&self) -> &TrieNode {
+        self.words_trie.root()
+    }
+
     pub fn lookup(&self, word: &str) -> HashSet<&str> {
         self.words_trie.lookup(word).iter()
             .map(|&idx| self.words[idx].as_ref())
@@ -78,25 +155,60 @@ impl Dictionary {
             .collect()
     }
 
-    fn filter_and_normalize(words: Vec<String>) -> Vec<String> {
-        words.iter().filter(|word| word.is_ascii() && word.chars().all(char::is_alphabetic))
-            .map(|word| word.to_ascii_lowercase())
+    /// Every word along with its letter sum under the given scheme.
+    pub fn scored_words<'a, 'b>(&'a self, scheme: &'b ScoreScheme) -> impl Iterator<Item = (&'a str, u32)> + 'b
+        where 'a: 'b {
+        self.words.iter().zip(&self.sums).map(move |(word, sums)| {
+            let score = match scheme {
+                ScoreScheme::A1Z26 => sums.a1z26,
+                ScoreScheme::Scrabble => sums.scrabble,
+                ScoreScheme::Gematria => sums.gematria,
+                ScoreScheme::Custom(_) => scheme.score(word),
+            };
+            (word.as_ref(), score)
+        })
+    }
+
+    fn letter_sums(words: &[String]) -> Vec<LetterSums> {
+        words.iter().map(|word| LetterSums {
+            a1z26: ScoreScheme::A1Z26.score(word),
+            scrabble: ScoreScheme::Scrabble.score(word),
+            gematria: ScoreScheme::Gematria.score(word),
+        }).collect()
+    }
+
+    /// Parses `word [count] [#tag...]` lines, dropping any that don't fit or
+    /// whose word isn't plain ASCII letters.
+    fn filter_and_normalize(lines: Vec<String>) -> Vec<(String, u64, Vec<String>)> {
+        lines.iter().filter_map(|line| {
+            let mut fields = line.split_whitespace().peekable();
+            let word = fields.next()?;
+            let frequency = match fields.next_if(|field| !field.starts_with('#')) {
+                None => 0,
+                Some(count) => str::parse::<u64>(count).ok()?,
+            };
+            let tags = fields
+                .map(|field| field.strip_prefix('#').filter(|tag| !tag.is_empty()).map(str::to_string))
+                .collect::<Option<Vec<String>>>()?;
+            Some((word, frequency, tags))
+        })
+            .filter(|(word, _, _)| word.is_ascii() && word.chars().all(char::is_alphabetic))
+            .map(|(word, frequency, tags)| (word.to_ascii_lowercase(), frequency, tags))
             .collect()
     }
 
     fn new_anagram_trie(words: &[String]) -> Trie {
         let mut trie = Trie::new();
-        for i in 0..words.len() {
-            trie.add(&sort_letters(&words[i]), i);
+        for (i, word) in words.iter().enumerate() {
+            trie.add(&sort_letters(word), i);
         }
         trie
     }
 
     fn new_word_trie(words: &[String]) -> Trie {
         let mut trie = Trie::new();
-        for i in 0..words.len() {
-            trie.add(&words[i], i);
-
+        for (i, word) in words.iter().enumerate() {
+            trie.add(word, i);
         }
         trie
     }
@@ -114,4 +226,39 @@ mod tests {
         assert_eq!(dict.lookup_anagram("arb", true), HashSet::from_iter(vec!["bar"]));
         assert_eq!(dict.lookup_anagram("foob", true).len(), 0);
     }
+
+    #[test]
+    fn frequencies() {
+        let words = vec!["foo 12".into(), "bar".into(), "baz x".into(), "it's 3".into(), "Qux\t4".into()];
+        let dict = Dictionary::new(words);
+        assert_eq!(dict.words(), HashSet::from_iter(vec!["foo", "bar", "qux"]));
+        assert_eq!(dict.frequency(0), 12);
+        assert_eq!(dict.frequency(1), 0);
+        assert_eq!(dict.total_frequency(), 16);
+    }
+
+    #[test]
+    fn tags() {
+        let words = vec!["foo 12 #noun #short".into(), "bar #noun".into(), "baz #".into(), "qux 3 x #noun".into()];
+        let dict = Dictionary::new(words);
+        assert_eq!(dict.words(), HashSet::from_iter(vec!["foo", "bar"]));
+        assert_eq!(dict.frequency(0), 12);
+        assert!(dict.has_tag("foo", "short"));
+        assert!(dict.has_tag("Bar", "noun"));
+        assert!(!dict.has_tag("bar", "short"));
+        assert!(dict.has_tag("bar", "*"));
+        assert!(!dict.has_tag("b.r", "*"));
+        assert!(!dict.has_tag("zap", "*"));
+    }
+
+    #[test]
+    fn scores_survive_cache() {
+        let words = vec!["foo".into(), "bar".into()];
+        let dict = Dictionary::new(words);
+        let mut cache = Vec::new();
+        dict.write_cache(&mut cache).unwrap();
+        let cached = Dictionary::new_from_cache(cache.as_slice()).unwrap();
+        let scores: Vec<(&str, u32)> = cached.scored_words(&ScoreScheme::A1Z26).collect();
+        assert_eq!(scores, vec![("foo", 36), ("bar", 21)]);
+    }
 }
@@ -0,0 +1,171 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Morse,
+    Braille,
+    Semaphore,
+    Binary,
+    Nato,
+}
+
+const MORSE: [&str; 26] = [
+    ".-", "-...", "-.-.", "-..", ".", "..-.", "--.", "....", "..", ".---", "-.-", ".-..", "--",
+    "-.", "---", ".--.", "--.-", ".-.", "...", "-", "..-", "...-", ".--", "-..-", "-.--", "--..",
+];
+
+/// Raised dots of each letter's Braille cell, numbered 1-3 down the left
+/// column and 4-6 down the right.
+const BRAILLE: [&str; 26] = [
+    "1", "12", "14", "145", "15", "124", "1245", "125", "24", "245", "13", "123", "134",
+    "1345", "135", "1234", "12345", "1235", "234", "2345", "136", "1236", "2456", "1346",
+    "13456", "1356",
+];
+
+/// Positions of both flags, numbered 1-8 clockwise from straight down as
+/// seen by the reader.
+const SEMAPHORE: [&str; 26] = [
+    "12", "13", "14", "15", "16", "17", "18", "23", "24", "57", "25", "26", "27",
+    "28", "34", "35", "36", "37", "38", "45", "46", "58", "67", "68", "47", "78",
+];
+
+const NATO: [&str; 26] = [
+    "alfa", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india",
+    "juliett", "kilo", "lima", "mike", "november", "oscar", "papa", "quebec", "romeo",
+    "sierra", "tango", "uniform", "victor", "whiskey", "xray", "yankee", "zulu",
+];
+
+fn letter_index(letter: char) -> Option<usize> {
+    let letter = letter.to_ascii_lowercase();
+    if letter.is_ascii_lowercase() {
+        Some(letter as usize - 'a' as usize)
+    } else {
+        None
+    }
+}
+
+fn index_letter(idx: usize) -> char {
+    (b'a' + idx as u8) as char
+}
+
+/// Puts digit-set codes like Braille cells and semaphore positions into a
+/// canonical order, so "21" and "12" decode the same way.
+fn sorted_digits(code: &str) -> String {
+    let mut digits: Vec<char> = code.chars().collect();
+    digits.sort_unstable();
+    digits.dedup();
+    digits.into_iter().collect()
+}
+
+impl Encoding {
+    /// The code for a single letter.
+    pub fn encode_letter(&self, letter: char) -> Option<String> {
+        let idx = letter_index(letter)?;
+        let code = match self {
+            Encoding::Morse => MORSE[idx].to_string(),
+            Encoding::Braille => BRAILLE[idx].to_string(),
+            Encoding::Semaphore => SEMAPHORE[idx].to_string(),
+            Encoding::Binary => format!("{:05b}", idx + 1),
+            Encoding::Nato => NATO[idx].to_string(),
+        };
+        Some(code)
+    }
+
+    /// The letter for a single code, or None if it isn't valid.
+    pub fn decode_letter(&self, code: &str) -> Option<char> {
+        let code = code.to_ascii_lowercase();
+        let idx = match self {
+            Encoding::Morse => MORSE.iter().position(|&morse| morse == code),
+            Encoding::Braille => BRAILLE.iter().position(|&cell| cell == sorted_digits(&code)),
+            Encoding::Semaphore => SEMAPHORE.iter().position(|&flags| flags == sorted_digits(&code)),
+            Encoding::Binary => usize::from_str_radix(&code, 2).ok()
+                .filter(|&value| code.len() == 5 && (1..=26).contains(&value))
+                .map(|value| value - 1),
+            Encoding::Nato => match code.as_ref() {
+                "alpha" => Some(0),
+                "juliet" => Some(9),
+                "x-ray" => Some(23),
+                _ => NATO.iter().position(|&word| word == code),
+            },
+        };
+        idx.map(index_letter)
+    }
+
+    /// Decodes a spaced message. Letter codes are separated by whitespace or
+    /// commas, and words by '/'. Binary codes may also be run together.
+    pub fn decode(&self, message: &str) -> Option<String> {
+        let mut words = Vec::new();
+        for word in message.split('/') {
+            let mut decoded = String::new();
+            for code in word.split(|c: char| c.is_whitespace() || c == ',').filter(|code| !code.is_empty()) {
+                if *self == Encoding::Binary && code.len() > 5 && code.len() % 5 == 0 {
+                    for chunk in code.as_bytes().chunks(5) {
+                        decoded.push(self.decode_letter(std::str::from_utf8(chunk).ok()?)?);
+                    }
+                } else {
+                    decoded.push(self.decode_letter(code)?);
+                }
+            }
+            if !decoded.is_empty() {
+                words.push(decoded);
+            }
+        }
+        if words.is_empty() {
+            None
+        } else {
+            Some(words.join(" "))
+        }
+    }
+
+    /// Encodes a message, separating letter codes by spaces and words by " / ".
+    pub fn encode(&self, message: &str) -> Option<String> {
+        let mut words = Vec::new();
+        for word in message.split_whitespace() {
+            let codes: Option<Vec<String>> = word.chars()
+                .map(|letter| self.encode_letter(letter))
+                .collect();
+            words.push(codes?.join(" "));
+        }
+        if words.is_empty() {
+            None
+        } else {
+            Some(words.join(" / "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_round_trip() {
+        let encodings = [Encoding::Morse, Encoding::Braille, Encoding::Semaphore, Encoding::Binary, Encoding::Nato];
+        for encoding in &encodings {
+            for letter in "abcdefghijklmnopqrstuvwxyz".chars() {
+                let code = encoding.encode_letter(letter).unwrap();
+                assert_eq!(encoding.decode_letter(&code), Some(letter), "{:?} {}", encoding, code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Encoding::Morse.decode(".... --- ... . / ... .... ."), Some("hose she".to_string()));
+        assert_eq!(Encoding::Morse.decode("...,---,..."), Some("sos".to_string()));
+        assert_eq!(Encoding::Morse.decode(".......- ..."), None);
+        assert_eq!(Encoding::Braille.decode("125 135 432 15"), Some("hose".to_string()));
+        assert_eq!(Encoding::Semaphore.decode("23 34 83 16"), Some("hose".to_string()));
+        assert_eq!(Encoding::Binary.decode("01000 01111 10011 00101"), Some("hose".to_string()));
+        assert_eq!(Encoding::Binary.decode("01000011111001100101"), Some("hose".to_string()));
+        assert_eq!(Encoding::Binary.decode("11111"), None);
+        assert_eq!(Encoding::Nato.decode("Hotel Oscar Sierra Echo / x-ray"), Some("hose x".to_string()));
+        assert_eq!(Encoding::Nato.decode(""), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(Encoding::Morse.encode("sos hi"), Some("... --- ... / .... ..".to_string()));
+        assert_eq!(Encoding::Binary.encode("az"), Some("00001 11010".to_string()));
+        assert_eq!(Encoding::Nato.encode("hi"), Some("hotel india".to_string()));
+        assert_eq!(Encoding::Braille.encode("h1"), None);
+    }
+}
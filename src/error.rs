@@ -0,0 +1,46 @@
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug)]
+pub enum SiftError {
+    InvalidRegExp,
+    InvalidCharacters,
+    InvalidCommand,
+    MissingLetters,
+    InvalidNumber,
+    FileIOError,
+    SerializationError,
+    DeserializationError,
+    InvalidMapping,
+    InvalidGrid,
+    InvalidGuess,
+    InvalidTemplate,
+    MissingStage,
+    UnknownWord,
+}
+
+impl fmt::Display for SiftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            SiftError::InvalidRegExp => "invalid regular expression",
+            SiftError::InvalidCharacters => "invalid characters",
+            SiftError::InvalidCommand => "invalid command",
+            SiftError::MissingLetters => "missing letters",
+            SiftError::InvalidNumber => "invalid number",
+            SiftError::FileIOError => "couldn't read or write file",
+            SiftError::SerializationError => "couldn't save dictionary",
+            SiftError::DeserializationError => "couldn't load dictionary",
+            SiftError::InvalidMapping => "invalid letter mapping",
+            SiftError::InvalidGrid => "invalid grid",
+            SiftError::InvalidGuess => "invalid guess",
+            SiftError::InvalidTemplate => "invalid placeholder",
+            SiftError::MissingStage => "placeholder refers to a missing pipeline stage",
+            SiftError::UnknownWord => "word isn't in the dictionary",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for SiftError {}
@@ -0,0 +1,86 @@
+/// Rule for picking letters out of a phrase.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selection {
+    Odd,
+    Even,
+    /// Every nth letter, starting from the given 1-based offset.
+    Nth(usize, usize),
+    First,
+    Last,
+    /// Letters at the given 1-based positions, in the order given.
+    Positions(Vec<usize>),
+}
+
+/// What to look up once the letters have been extracted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    Plain,
+    Anagram,
+    Reverse,
+}
+
+/// Splits a phrase into words on anything that isn't a letter, so phrases
+/// can be written as "the quick fox" or "the_quick_fox".
+pub fn phrase_words(phrase: &str) -> Vec<&str> {
+    phrase.split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Extracts letters from the phrase according to the selection, returning
+/// None if the selection refers to letters the phrase doesn't have.
+pub fn extract(phrase: &str, selection: &Selection) -> Option<String> {
+    let letters: Vec<char> = phrase.chars()
+        .filter(|c| c.is_alphabetic())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let extracted: String = match selection {
+        Selection::Odd => letters.iter().step_by(2).collect(),
+        Selection::Even => letters.iter().skip(1).step_by(2).collect(),
+        Selection::Nth(n, offset) => {
+            if *n == 0 || *offset == 0 {
+                return None;
+            }
+            letters.iter().skip(offset - 1).step_by(*n).collect()
+        },
+        Selection::First => phrase_words(phrase).iter()
+            .filter_map(|word| word.chars().next())
+            .collect(),
+        Selection::Last => phrase_words(phrase).iter()
+            .filter_map(|word| word.chars().last())
+            .collect(),
+        Selection::Positions(positions) => {
+            let mut extracted = String::new();
+            for &pos in positions {
+                if pos == 0 || pos > letters.len() {
+                    return None;
+                }
+                extracted.push(letters[pos - 1]);
+            }
+            extracted
+        },
+    };
+    Some(extracted.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phrase_words() {
+        assert_eq!(phrase_words("the quick_brown,fox"), vec!["the", "quick", "brown", "fox"]);
+    }
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(extract("abc def", &Selection::Odd), Some("ace".to_string()));
+        assert_eq!(extract("abc def", &Selection::Even), Some("bdf".to_string()));
+        assert_eq!(extract("abcdefg", &Selection::Nth(3, 2)), Some("be".to_string()));
+        assert_eq!(extract("abcdefg", &Selection::Nth(0, 1)), None);
+        assert_eq!(extract("The Quick fox", &Selection::First), Some("tqf".to_string()));
+        assert_eq!(extract("the quick fox", &Selection::Last), Some("ekx".to_string()));
+        assert_eq!(extract("abc", &Selection::Positions(vec![3, 1])), Some("ca".to_string()));
+        assert_eq!(extract("abc", &Selection::Positions(vec![4])), None);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::sifter::Sifter;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Conditions every result of a command must meet. Lengths count letters
+/// only, so a multi-word result like "hose shoe" has length 8.
+#[derive(Clone, Debug, Default)]
+pub struct ResultFilter {
+    pub len: Option<usize>,
+    pub min_len: Option<usize>,
+    pub max_len: Option<usize>,
+    /// Letters the word must have, counting repeats.
+    pub contains: Option<String>,
+    /// Letters the word mustn't have.
+    pub excludes: Option<String>,
+    pub starts: Option<String>,
+    pub ends: Option<String>,
+    /// A regex the whole word must match.
+    pub pattern: Option<Regex>,
+    /// A tag the word must carry in the dictionary, or "*" for any word that
+    /// is in the dictionary.
+    pub in_dict: Option<String>,
+}
+
+fn letter_counts(letters: &str) -> HashMap<char, usize> {
+    let mut counts = HashMap::new();
+    for letter in letters.chars().filter(|c| c.is_alphabetic()) {
+        *counts.entry(letter.to_ascii_lowercase()).or_insert(0) += 1;
+    }
+    counts
+}
+
+impl ResultFilter {
+    pub fn is_empty(&self) -> bool {
+        self.len.is_none() && self.min_len.is_none() && self.max_len.is_none()
+            && self.contains.is_none() && self.excludes.is_none()
+            && self.starts.is_none() && self.ends.is_none()
+            && self.pattern.is_none() && self.in_dict.is_none()
+    }
+
+    pub fn keeps(&self, word: &str, sifter: &Sifter) -> bool {
+        let lowered = word.to_lowercase();
+        let len = lowered.chars().filter(|c| c.is_alphabetic()).count();
+        if self.len.is_some_and(|n| len != n)
+            || self.min_len.is_some_and(|n| len < n)
+            || self.max_len.is_some_and(|n| len > n) {
+            return false;
+        }
+        if let Some(contains) = &self.contains {
+            let have = letter_counts(&lowered);
+            if letter_counts(contains).iter().any(|(letter, n)| have.get(letter).unwrap_or(&0) < n) {
+                return false;
+            }
+        }
+        if let Some(excludes) = &self.excludes {
+            if excludes.to_lowercase().chars().any(|letter| lowered.contains(letter)) {
+                return false;
+            }
+        }
+        if self.starts.as_ref().is_some_and(|starts| !lowered.starts_with(&starts.to_lowercase()))
+            || self.ends.as_ref().is_some_and(|ends| !lowered.ends_with(&ends.to_lowercase())) {
+            return false;
+        }
+        if self.pattern.as_ref().is_some_and(|pattern| !pattern.is_match(word)) {
+            return false;
+        }
+        self.in_dict.as_ref().is_none_or(|tag| sifter.has_tag(word, tag))
+    }
+}
+
+/// Anchors a pattern so that it has to match a whole word.
+pub fn whole_word_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps() {
+        let sifter = Sifter::new_from_words("hose #clothing\nshoe #clothing\nrose\n".as_bytes());
+        let keeps = |filter: &ResultFilter, word: &str| filter.keeps(word, &sifter);
+        assert!(ResultFilter::default().is_empty());
+        assert!(keeps(&ResultFilter::default(), "anything"));
+
+        let filter = ResultFilter { len: Some(4), ..Default::default() };
+        assert!(!filter.is_empty());
+        assert!(keeps(&filter, "hose"));
+        assert!(!keeps(&filter, "horse"));
+        assert!(keeps(&ResultFilter { min_len: Some(8), max_len: Some(8), ..Default::default() }, "hose shoe"));
+
+        let filter = ResultFilter { contains: Some("ss".to_string()), excludes: Some("E".to_string()), ..Default::default() };
+        assert!(keeps(&filter, "moss"));
+        assert!(!keeps(&filter, "most"));
+        assert!(!keeps(&filter, "mess"));
+
+        let filter = ResultFilter { starts: Some("h".to_string()), ends: Some("se".to_string()), ..Default::default() };
+        assert!(keeps(&filter, "Horse"));
+        assert!(!keeps(&filter, "rose"));
+
+        let filter = ResultFilter { pattern: Some(whole_word_regex("h.se|r.*").unwrap()), ..Default::default() };
+        assert!(keeps(&filter, "hose"));
+        assert!(keeps(&filter, "rose"));
+        assert!(!keeps(&filter, "horse"));
+
+        let filter = ResultFilter { in_dict: Some("clothing".to_string()), ..Default::default() };
+        assert!(keeps(&filter, "shoe"));
+        assert!(!keeps(&filter, "rose"));
+        assert!(keeps(&ResultFilter { in_dict: Some("*".to_string()), ..Default::default() }, "rose"));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::SiftError;
+use std::collections::HashSet;
+
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    (0, -1), (0, 1),
+    (1, -1), (1, 0), (1, 1),
+];
+
+/// How words may be traced through a grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridOptions {
+    /// Whether diagonal cells count as adjacent.
+    pub diagonals: bool,
+    /// Whether a cell may be used more than once in a word.
+    pub reuse: bool,
+    /// Whether paths may run off one edge and continue from the opposite one.
+    pub wrap: bool,
+    /// Whether words must run in a straight line, as in a word search.
+    pub straight: bool,
+    pub min_len: usize,
+}
+
+impl Default for GridOptions {
+    fn default() -> GridOptions {
+        GridOptions { diagonals: true, reuse: false, wrap: false, straight: false, min_len: 3 }
+    }
+}
+
+impl GridOptions {
+    pub fn directions(&self) -> &'static [(isize, isize)] {
+        if self.diagonals {
+            &ALL_DIRECTIONS
+        } else {
+            &ORTHOGONAL
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    rows: usize,
+    cols: usize,
+    cells: Vec<char>,
+}
+
+impl Grid {
+    /// Parses a grid from rows of letters separated by whitespace, '/' or ','.
+    /// Every row must be the same length.
+    pub fn parse(text: &str) -> Result<Grid, SiftError> {
+        let rows: Vec<Vec<char>> = text.split(|c: char| c.is_whitespace() || c == '/' || c == ',')
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().map(|c| c.to_ascii_lowercase()).collect())
+            .collect();
+        let cols = rows.first().map(|row| row.len()).ok_or(SiftError::InvalidGrid)?;
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(SiftError::InvalidGrid);
+        }
+        Ok(Grid { rows: rows.len(), cols, cells: rows.concat() })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, (row, col): (usize, usize)) -> char {
+        self.cells[row * self.cols + col]
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.rows).flat_map(move |row| (0..self.cols).map(move |col| (row, col)))
+    }
+
+    /// The cell one step from the given one in a direction, if there is one.
+    pub fn step(&self, (row, col): (usize, usize), (d_row, d_col): (isize, isize), wrap: bool) -> Option<(usize, usize)> {
+        let row = row as isize + d_row;
+        let col = col as isize + d_col;
+        let (rows, cols) = (self.rows as isize, self.cols as isize);
+        if wrap {
+            Some((row.rem_euclid(rows) as usize, col.rem_euclid(cols) as usize))
+        } else if (0..rows).contains(&row) && (0..cols).contains(&col) {
+            Some((row as usize, col as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Finds a word running in a straight line in any of the 8 directions,
+    /// returning the first path found in reading order.
+    pub fn find_word(&self, word: &str) -> Option<Vec<(usize, usize)>> {
+        let letters: Vec<char> = word.chars().map(|c| c.to_ascii_lowercase()).collect();
+        if letters.is_empty() {
+            return None;
+        }
+        for start in self.cells() {
+            for &direction in &ALL_DIRECTIONS {
+                let mut path = vec![start];
+                while path.len() < letters.len() {
+                    match self.step(*path.last().unwrap(), direction, false) {
+                        Some(next) => path.push(next),
+                        None => break,
+                    }
+                }
+                if path.len() == letters.len() && path.iter().zip(&letters).all(|(&cell, &letter)| self.get(cell) == letter) {
+                    return Some(path);
+                }
+            }
+        }
+        None
+    }
+
+    /// The letters of cells not in any of the given paths, in reading order.
+    pub fn leftover<'a, I>(&self, paths: I) -> String where I: IntoIterator<Item = &'a Vec<(usize, usize)>> {
+        let used: HashSet<&(usize, usize)> = paths.into_iter().flatten().collect();
+        self.cells()
+            .filter(|cell| !used.contains(cell))
+            .map(|cell| self.get(cell))
+            .collect()
+    }
+}
+
+/// Formats a path of cells as 1-based (row,col) pairs.
+pub fn format_path(path: &[(usize, usize)]) -> String {
+    path.iter()
+        .map(|(row, col)| format!("({},{})", row + 1, col + 1))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let grid = Grid::parse("abc DEF/ghi").unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (3, 3));
+        assert_eq!(grid.get((1, 0)), 'd');
+        assert_eq!(grid.cells().count(), 9);
+        assert!(Grid::parse("abc de").is_err());
+        assert!(Grid::parse("  ").is_err());
+    }
+
+    #[test]
+    fn test_step() {
+        let grid = Grid::parse("abc def").unwrap();
+        assert_eq!(grid.step((0, 0), (1, 1), false), Some((1, 1)));
+        assert_eq!(grid.step((0, 0), (-1, 0), false), None);
+        assert_eq!(grid.step((0, 0), (-1, 0), true), Some((1, 0)));
+        assert_eq!(grid.step((1, 2), (0, 1), true), Some((1, 0)));
+    }
+
+    #[test]
+    fn test_find_word() {
+        let grid = Grid::parse("cat xox god").unwrap();
+        assert_eq!(grid.find_word("cat"), Some(vec![(0, 0), (0, 1), (0, 2)]));
+        assert_eq!(grid.find_word("dog"), Some(vec![(2, 2), (2, 1), (2, 0)]));
+        assert_eq!(grid.find_word("cod"), Some(vec![(0, 0), (1, 1), (2, 2)]));
+        assert_eq!(grid.find_word("tog"), Some(vec![(0, 2), (1, 1), (2, 0)]));
+        assert_eq!(grid.find_word("cox"), None);
+        assert_eq!(grid.find_word(""), None);
+    }
+
+    #[test]
+    fn test_leftover() {
+        let grid = Grid::parse("cat xox god").unwrap();
+        let paths = vec![grid.find_word("cat").unwrap(), grid.find_word("dog").unwrap()];
+        assert_eq!(grid.leftover(&paths), "xox");
+    }
+
+    #[test]
+    fn test_format_path() {
+        assert_eq!(format_path(&[(0, 0), (1, 2)]), "(1,1) (2,3)");
+    }
+}
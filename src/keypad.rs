@@ -0,0 +1,58 @@
+const KEYPAD: [(char, &str); 8] = [
+    ('2', "abc"),
+    ('3', "def"),
+    ('4', "ghi"),
+    ('5', "jkl"),
+    ('6', "mno"),
+    ('7', "pqrs"),
+    ('8', "tuv"),
+    ('9', "wxyz"),
+];
+
+pub fn is_digits(input: &str) -> bool {
+    !input.is_empty() && input.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Turns a digit sequence into a trie pattern of letter classes, e.g. "43"
+/// becomes "[ghi][def]". Returns None if a digit has no letters.
+pub fn to_pattern(digits: &str) -> Option<String> {
+    let mut pattern = String::new();
+    for digit in digits.chars() {
+        let (_, letters) = KEYPAD.iter().find(|(key, _)| *key == digit)?;
+        pattern.push('[');
+        pattern.push_str(letters);
+        pattern.push(']');
+    }
+    Some(pattern)
+}
+
+/// Spells a word out on the keypad. Returns None if the word has anything
+/// other than letters.
+pub fn to_digits(word: &str) -> Option<String> {
+    word.chars()
+        .map(|letter| {
+            let letter = letter.to_ascii_lowercase();
+            KEYPAD.iter()
+                .find(|(_, letters)| letters.contains(letter))
+                .map(|(key, _)| *key)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pattern() {
+        assert_eq!(to_pattern("43"), Some("[ghi][def]".to_string()));
+        assert_eq!(to_pattern("401"), None);
+    }
+
+    #[test]
+    fn test_to_digits() {
+        assert_eq!(to_digits("Hose"), Some("4673".to_string()));
+        assert_eq!(to_digits("pqrs wxyz"), None);
+        assert_eq!(to_digits("wxyz"), Some("9999".to_string()));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::error::SiftError;
+use std::collections::{HashMap, HashSet};
+
+/// A Letter Boxed puzzle: letters around the sides of a box, where a word
+/// can't use two letters from the same side in a row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LetterBox {
+    sides: Vec<Vec<char>>,
+}
+
+impl LetterBox {
+    /// Parses sides separated by whitespace, '/' or ','. There must be at
+    /// least two sides, and no letter may appear twice.
+    pub fn parse(text: &str) -> Result<LetterBox, SiftError> {
+        let sides: Vec<Vec<char>> = text.split(|c: char| c.is_whitespace() || c == '/' || c == ',')
+            .filter(|side| !side.is_empty())
+            .map(|side| side.chars().map(|c| c.to_ascii_lowercase()).collect())
+            .collect();
+        let letters: Vec<char> = sides.concat();
+        let distinct: HashSet<&char> = letters.iter().collect();
+        if sides.len() < 2 || distinct.len() != letters.len() || letters.len() > 64
+            || !letters.iter().all(|c| c.is_ascii_lowercase()) {
+            return Err(SiftError::InvalidGrid);
+        }
+        Ok(LetterBox { sides })
+    }
+
+    /// Which side the letter is on, if it's in the box at all.
+    pub fn side(&self, letter: char) -> Option<usize> {
+        self.sides.iter().position(|side| side.contains(&letter))
+    }
+
+    fn letter_bit(&self, letter: char) -> Option<u64> {
+        self.sides.iter().flatten().position(|&c| c == letter).map(|idx| 1 << idx)
+    }
+
+    /// The set of box letters a word uses, as a bit per letter.
+    pub fn mask(&self, word: &str) -> u64 {
+        word.chars().filter_map(|letter| self.letter_bit(letter)).fold(0, |mask, bit| mask | bit)
+    }
+
+    /// The mask of a chain that uses every letter.
+    pub fn full_mask(&self) -> u64 {
+        let count = self.sides.iter().map(|side| side.len()).sum::<usize>();
+        if count == 64 { u64::MAX } else { (1 << count) - 1 }
+    }
+}
+
+struct ChainSearch<'a, 'b> {
+    letter_box: &'b LetterBox,
+    by_first: &'b HashMap<char, Vec<(&'a str, u64)>>,
+    dead_ends: HashSet<(u64, char, usize)>,
+    chain: Vec<&'a str>,
+    chains: Vec<Vec<&'a str>>,
+}
+
+impl<'a> ChainSearch<'a, '_> {
+    /// Extends the chain from a word ending in `last`, using exactly
+    /// `words_left` more words. Returns whether any full chain was found, so
+    /// states that can't finish are only explored once.
+    fn extend(&mut self, mask: u64, last: char, words_left: usize) -> bool {
+        if words_left == 0 {
+            if mask == self.letter_box.full_mask() {
+                self.chains.push(self.chain.clone());
+                return true;
+            }
+            return false;
+        }
+        if self.dead_ends.contains(&(mask, last, words_left)) {
+            return false;
+        }
+        let mut found = false;
+        let by_first = self.by_first;
+        for &(word, word_mask) in by_first.get(&last).into_iter().flatten() {
+            let next_last = word.chars().last().unwrap_or(last);
+            self.chain.push(word);
+            found |= self.extend(mask | word_mask, next_last, words_left - 1);
+            self.chain.pop();
+        }
+        if !found {
+            self.dead_ends.insert((mask, last, words_left));
+        }
+        found
+    }
+}
+
+/// The chains of fewest words, up to `max_words`, that use every letter in
+/// the box, where each word starts with the last letter of the one before.
+pub fn shortest_chains<'a>(letter_box: &LetterBox, words: &[&'a str], max_words: usize) -> Vec<Vec<&'a str>> {
+    let mut by_first: HashMap<char, Vec<(&'a str, u64)>> = HashMap::new();
+    for &word in words {
+        if let Some(first) = word.chars().next() {
+            by_first.entry(first).or_default().push((word, letter_box.mask(word)));
+        }
+    }
+    let mut search = ChainSearch { letter_box, by_first: &by_first, dead_ends: HashSet::new(), chain: Vec::new(), chains: Vec::new() };
+    for length in 1..=max_words {
+        for &word in words {
+            let last = match word.chars().last() {
+                Some(last) => last,
+                None => continue,
+            };
+            search.chain.push(word);
+            search.extend(letter_box.mask(word), last, length - 1);
+            search.chain.pop();
+        }
+        if !search.chains.is_empty() {
+            break;
+        }
+    }
+    search.chains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let letter_box = LetterBox::parse("ab/CD ef").unwrap();
+        assert_eq!(letter_box.side('c'), Some(1));
+        assert_eq!(letter_box.side('z'), None);
+        assert_eq!(letter_box.mask("face"), 0b110101);
+        assert_eq!(letter_box.full_mask(), 0b111111);
+        assert!(LetterBox::parse("abc").is_err());
+        assert!(LetterBox::parse("abc cde").is_err());
+    }
+
+    #[test]
+    fn test_shortest_chains() {
+        let letter_box = LetterBox::parse("ab cd ef").unwrap();
+        let words = vec!["ace", "eb", "bdf", "fad", "ebf"];
+        let mut chains = shortest_chains(&letter_box, &words, 3);
+        chains.sort();
+        assert_eq!(chains, vec![vec!["ace", "eb", "bdf"], vec!["ace", "ebf", "fad"]]);
+        assert!(shortest_chains(&letter_box, &words, 2).is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+
+/// Where something is in a query, as byte offsets from its start.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// The span covering both this one and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span { start: self.start.min(other.start), end: self.end.max(other.end) }
+    }
+}
+
+/// A mistake in a query, and where it is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new<S: Into<String>>(message: S, span: Span) -> ParseError {
+        ParseError { message: message.into(), span }
+    }
+
+    /// The query with the mistake underlined, and the message beneath it.
+    pub fn render(&self, query: &str) -> String {
+        let column = |offset: usize| query.get(..offset.min(query.len())).map_or(0, |text| text.chars().count());
+        let start = column(self.span.start);
+        let width = column(self.span.end).saturating_sub(start).max(1);
+        format!("{}\n{}{} {}", query, " ".repeat(start), "^".repeat(width), self.message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at {}..{})", self.message, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    /// A word, after its quotes and escapes are taken out. Quoted words are
+    /// never operators or command names.
+    Word { text: String, quoted: bool },
+    Pipe,
+    Open,
+    Close,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    /// The word's text, if the token is an unquoted word.
+    pub fn bare_word(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Word { text, quoted: false } => Some(text),
+            _ => None,
+        }
+    }
+}
+
+fn is_special(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '|' | '(' | ')' | '\'' | '"' | '\\')
+}
+
+/// Splits a query typed as one line into tokens:
+///
+/// * words are separated by whitespace
+/// * `|`, `(` and `)` stand on their own wherever they appear
+/// * `'...'` keeps everything inside as it is
+/// * `"..."` keeps everything inside but `\"` and `\\`
+/// * a backslash before whitespace or any of `|()'"\` keeps that character;
+///   before anything else it stays, so regexes like `\w+` need no quoting
+pub fn tokenize(query: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let kind = match c {
+            '|' => Some(TokenKind::Pipe),
+            '(' => Some(TokenKind::Open),
+            ')' => Some(TokenKind::Close),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            chars.next();
+            tokens.push(Token { kind, span: Span::new(start, start + 1) });
+            continue;
+        }
+        let mut text = String::new();
+        let mut quoted = false;
+        let mut end = start;
+        while let Some(&(idx, c)) = chars.peek() {
+            if c.is_whitespace() || matches!(c, '|' | '(' | ')') {
+                break;
+            }
+            chars.next();
+            end = idx + c.len_utf8();
+            match c {
+                '\'' | '"' => {
+                    quoted = true;
+                    let close = loop {
+                        match chars.next() {
+                            Some((idx, inner)) if inner == c => break idx,
+                            Some((_, '\\')) if c == '"' && matches!(chars.peek(), Some((_, '"' | '\\'))) => {
+                                text.push(chars.next().unwrap().1);
+                            },
+                            Some((_, inner)) => text.push(inner),
+                            None => return Err(ParseError::new("unterminated quote", Span::new(idx, query.len()))),
+                        }
+                    };
+                    end = close + 1;
+                },
+                '\\' => match chars.peek() {
+                    Some(&(next_idx, next)) if is_special(next) => {
+                        chars.next();
+                        text.push(next);
+                        end = next_idx + next.len_utf8();
+                    },
+                    _ => text.push('\\'),
+                },
+                _ => text.push(c),
+            }
+        }
+        tokens.push(Token { kind: TokenKind::Word { text, quoted }, span: Span::new(start, end) });
+    }
+    Ok(tokens)
+}
+
+/// Tokens for arguments the shell has already split, given with the text
+/// they're joined into for error messages. Each argument is a word unless
+/// it's exactly `|`, `(` or `)`, so nothing inside one needs escaping.
+pub fn tokens_from_args<S: AsRef<str>>(args: &[S]) -> (String, Vec<Token>) {
+    let mut joined = String::new();
+    let mut tokens = Vec::new();
+    for arg in args {
+        let arg = arg.as_ref();
+        if !joined.is_empty() {
+            joined.push(' ');
+        }
+        let span = Span::new(joined.len(), joined.len() + arg.len());
+        joined.push_str(arg);
+        let kind = match arg {
+            "|" => TokenKind::Pipe,
+            "(" => TokenKind::Open,
+            ")" => TokenKind::Close,
+            _ => TokenKind::Word { text: arg.to_string(), quoted: false },
+        };
+        tokens.push(Token { kind, span });
+    }
+    (joined, tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(query: &str) -> Vec<String> {
+        tokenize(query).unwrap().into_iter()
+            .map(|token| match token.kind {
+                TokenKind::Word { text, .. } => text,
+                TokenKind::Pipe => "|".to_string(),
+                TokenKind::Open => "(".to_string(),
+                TokenKind::Close => ")".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(words("anagram  horse"), vec!["anagram", "horse"]);
+        assert_eq!(words("(anagram horse)|bank %"), vec!["(", "anagram", "horse", ")", "|", "bank", "%"]);
+        assert_eq!(words("extract 'hungry owls' \"see \\\"eagles\\\"\""), vec!["extract", "hungry owls", "see \"eagles\""]);
+        assert_eq!(words("'h(o|a)se' h\\(o\\|a\\)se \\w+"), vec!["h(o|a)se", "h(o|a)se", "\\w+"]);
+        assert_eq!(words("a'b c'd"), vec!["ab cd"]);
+        assert_eq!(words("''"), vec![""]);
+    }
+
+    #[test]
+    fn test_spans() {
+        let tokens = tokenize("bank 'a b' | x").unwrap();
+        let spans: Vec<Span> = tokens.iter().map(|token| token.span).collect();
+        assert_eq!(spans, vec![Span::new(0, 4), Span::new(5, 10), Span::new(11, 12), Span::new(13, 14)]);
+        assert_eq!(tokens[1].bare_word(), None);
+        assert_eq!(tokens[3].bare_word(), Some("x"));
+
+        let err = tokenize("anagram 'horse").unwrap_err();
+        assert_eq!(err.span, Span::new(8, 14));
+        assert_eq!(err.render("anagram 'horse"), "anagram 'horse\n        ^^^^^^ unterminated quote");
+    }
+
+    #[test]
+    fn test_tokens_from_args() {
+        let (joined, tokens) = tokens_from_args(&["extract", "hungry owls", "|", "bank"]);
+        assert_eq!(joined, "extract hungry owls | bank");
+        assert_eq!(tokens[1].span, Span::new(8, 19));
+        assert_eq!(tokens[2].kind, TokenKind::Pipe);
+    }
+}
@@ -0,0 +1,47 @@
+//! Wordplay searches over a word list: anagrams, letter banks, regexes,
+//! deletions and additions, and puzzle solvers built on them.
+//!
+//! ```
+//! use sift::{Sifter, SiftCommand};
+//!
+//! let sifter = Sifter::new_from_words("hose\nshoe\nhoes\nrose\n".as_bytes());
+//! let command = SiftCommand::Anagram("hose".to_string());
+//! let words: Vec<String> = command.run(&sifter).iter().map(|m| m.to_string()).collect();
+//! assert_eq!(words, vec!["hoes", "shoe"]);
+//! ```
+//!
+//! Queries in the grammar the command line and web page share are parsed
+//! with `query::Query`. The `cli` feature, on by default, adds what the
+//! binary needs, and `wasm` the bindings for the web page.
+
+pub mod error;
+pub mod dictionary;
+pub mod trie;
+pub mod sifter;
+pub mod sift_command;
+pub mod extract;
+pub mod score;
+pub mod keypad;
+pub mod encoding;
+pub mod grid;
+pub mod crossword;
+pub mod wordle;
+pub mod letterbox;
+pub mod sequence;
+pub mod filter;
+pub mod output;
+pub mod template;
+pub mod parallel;
+pub mod budget;
+pub mod lexer;
+pub mod argparse;
+pub mod query;
+#[cfg(test)] mod test_utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use crate::error::SiftError;
+pub use crate::dictionary::Dictionary;
+pub use crate::sifter::Sifter;
+pub use crate::budget::{Budget, Work};
+pub use crate::sift_command::{SiftCommand, SiftMatch, SetOp, SortOrder};
@@ -1,19 +1,27 @@
-pub mod dictionary;
-pub mod trie;
-pub mod sifter;
-pub mod sift_command;
-#[cfg(test)] mod test_utils;
-pub mod argparse;
-
-use clap::{Arg, SubCommand, ArgMatches};
+mod serve;
+
+use clap::{App, AppSettings, Arg, SubCommand, ArgMatches};
 use atty::Stream;
-use crate::sifter::Sifter;
-use crate::sift_command::SiftCommand;
-use crate::argparse::{SiftError, get_app, parse_command};
-use std::io::{self, stdin, Read, Write};
+use sift::{Budget, Sifter, SiftCommand, SiftError, Work};
+use sift::output::{OutputMode, PipedOutput, input_stages, single_run_lines};
+use sift::template::Quoting;
+use sift::argparse::help;
+use sift::lexer::{ParseError, TokenKind, tokens_from_args};
+use sift::query::Query;
+use crate::serve::{ServeOptions, serve};
+use std::io::{self, stdin, BufRead, Read, Write};
+use std::time::Duration;
 
 fn main() {
-    let app = get_app()
+    let commands = help(None).unwrap();
+    let app = App::new("sift")
+        .after_help(commands.as_str())
+        .setting(AppSettings::TrailingVarArg)
+        .setting(AppSettings::AllowLeadingHyphen)
+        .setting(AppSettings::DisableHelpSubcommand)
+        .arg(Arg::with_name("query")
+            .help("a command and its arguments, or a regex")
+            .multiple(true))
         .arg(Arg::with_name("cache")
             .help("Path to a cached dictionary file")
             .short("c")
@@ -24,6 +32,34 @@ fn main() {
             .short("d")
             .long("dict")
             .takes_value(true))
+        .arg(Arg::with_name("output")
+            .help("How to print results when piped to: input => result pairs, distinct results only, results grouped by input, or inputs grouped by result")
+            .short("o")
+            .long("output")
+            .possible_values(&["pairs", "results", "grouped", "inverted"])
+            .default_value("pairs"))
+        .arg(Arg::with_name("count")
+            .help("Print how many results there are instead of the results, per input when piped to")
+            .long("count"))
+        .arg(Arg::with_name("stats")
+            .help("Print the total number of results and how many there are of each length")
+            .long("stats")
+            .conflicts_with("count"))
+        .arg(Arg::with_name("max-patterns")
+            .help("Most patterns any one command may try, such as add or change with a large -n")
+            .long("max-patterns")
+            .takes_value(true))
+        .arg(Arg::with_name("max-results")
+            .help("Most results to print for each run of the command (each input line, when piped to)")
+            .long("max-results")
+            .takes_value(true))
+        .arg(Arg::with_name("timeout")
+            .help("Seconds to run for before printing the results found so far")
+            .long("timeout")
+            .takes_value(true))
+        .arg(Arg::with_name("raw")
+            .help("Splice piped words into regular expressions as regex syntax, rather than matching them literally")
+            .long("raw"))
         .subcommand(SubCommand::with_name("create-cache")
             .about("create a dictionary cache file")
             .arg(Arg::with_name("dict-path")
@@ -31,7 +67,41 @@ fn main() {
                 .index(1))
             .arg(Arg::with_name("output-path")
                 .help("path where cached file will reside")
-                .index(2)));
+                .index(2)))
+        .subcommand(SubCommand::with_name("serve")
+            .about("answer queries over HTTP on localhost, returning JSON")
+            .arg(Arg::with_name("port")
+                .help("port to listen on")
+                .long("port")
+                .takes_value(true)
+                .default_value("8080"))
+            .arg(Arg::with_name("timeout")
+                .help("seconds to wait for a query's results")
+                .long("timeout")
+                .takes_value(true)
+                .default_value("10"))
+            .arg(Arg::with_name("max-results")
+                .help("most results to send back for one query")
+                .long("max-results")
+                .takes_value(true)
+                .default_value("1000"))
+            .arg(Arg::with_name("max-query-len")
+                .help("longest query to accept, in bytes")
+                .long("max-query-len")
+                .takes_value(true)
+                .default_value("1000"))
+            .arg(Arg::with_name("max-running")
+                .help("most queries to run at once")
+                .long("max-running")
+                .takes_value(true)
+                .default_value("8"))
+            .arg(Arg::with_name("max-connections")
+                .help("most connections to handle at once, with as many again waiting")
+                .long("max-connections")
+                .takes_value(true)
+                .default_value("32")))
+        .subcommand(SubCommand::with_name("repl")
+            .about("read queries line by line, with pipes, parentheses and quoting as on the web page"));
 
     let matches = app.get_matches();
 
@@ -45,13 +115,136 @@ fn main() {
     }
 
     let sifter = load_sifter(&matches).unwrap();
+    let budget = match get_budget(&matches) {
+        Ok(budget) => budget,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        },
+    };
+
+    if let ("serve", Some(sub_m)) = matches.subcommand() {
+        // serve has its own --timeout and --max-results, with defaults suited
+        // to a server, so the ones for a single query would be ambiguous.
+        if budget.timeout.is_some() || budget.max_results.is_some() {
+            eprintln!("pass --timeout and --max-results after serve, e.g. `sift serve --timeout 5`");
+            return;
+        }
+        let options = get_serve_options(sub_m);
+        match (sub_m.value_of("port").unwrap().parse::<u16>(), options) {
+            (Ok(port), Ok(options)) => {
+                let options = ServeOptions { max_patterns: budget.max_patterns, ..options };
+                if let Err(err) = serve(port, sifter, options) {
+                    eprintln!("{}", err);
+                }
+            },
+            _ => eprintln!("{:?}", SiftError::InvalidNumber),
+        }
+        return;
+    }
+    let output_mode = if matches.is_present("count") {
+        OutputMode::Count
+    } else if matches.is_present("stats") {
+        OutputMode::Stats
+    } else {
+        matches.value_of("output").and_then(OutputMode::from_name).unwrap_or(OutputMode::Pairs)
+    };
+    let quoting = if matches.is_present("raw") { Quoting::Raw } else { Quoting::Literal };
+
+    if matches.subcommand_name() == Some("repl") {
+        repl(&sifter, budget, quoting);
+        return;
+    }
+    let args: Vec<&str> = matches.values_of("query").map(|args| args.collect()).unwrap_or_default();
+    if args.first() == Some(&"help") || args.iter().any(|&arg| arg == "--help" || arg == "-h") {
+        let topic = if args.first() == Some(&"help") { args.get(1) } else { args.first() };
+        match help(topic.cloned()) {
+            Ok(text) => println!("{}", text),
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    }
+    let command = parse_command_line(&args)
+        .and_then(|command| command.check(&sifter).map(|_| command).map_err(|err| err.to_string()));
+    match command {
+        Ok(command) => {
+            let work = budget.start();
+            run(&sifter, command, output_mode, quoting, &work);
+            if work.is_partial() {
+                eprintln!("{}", CUT_SHORT);
+            }
+        },
+        Err(err) => eprintln!("{}", err),
+    }
+}
+
+/// Parses the command given on the command line. Pipes between commands go
+/// through the shell, so only one stage is allowed.
+fn parse_command_line(args: &[&str]) -> Result<SiftCommand, String> {
+    if args.is_empty() {
+        return Err("expected a command or regex; `sift help` lists the commands".to_string());
+    }
+    let (text, tokens) = tokens_from_args(args);
+    let mut stages = Query::from_tokens(&tokens).map_err(|err| err.render(&text))?.into_stages();
+    if stages.len() > 1 {
+        let pipe = tokens.iter().find(|token| token.kind == TokenKind::Pipe).unwrap();
+        let err = ParseError::new("pipe sift into another sift instead", pipe.span);
+        return Err(err.render(&text));
+    }
+    Ok(stages.remove(0))
+}
 
-    match parse_command(&matches) {
-        Ok(command) => run(&sifter, command),
-        Err(err) => eprintln!("{:?}", err),
+/// What to say when a limit on a query's work stopped it early.
+const CUT_SHORT: &str = "stopped early by --max-patterns, --max-results or --timeout; some results may be missing";
+
+/// Runs each line read from stdin as a query, prompting for it when stdin is
+/// a terminal. `help` lists the commands, and `help <command>` one command's
+/// options. Each query gets the whole budget.
+fn repl(sifter: &Sifter, budget: Budget, quoting: Quoting) {
+    let mut stdout = io::stdout();
+    let interactive = atty::is(Stream::Stdin);
+    let stdin = stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("sift> ");
+            let _ = stdout.flush();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            None => continue,
+            Some("quit") | Some("exit") => break,
+            Some("help") => match help(words.next()) {
+                Ok(text) => print(&mut stdout, &text),
+                Err(err) => eprintln!("{}", err),
+            },
+            Some(_) => match Query::parse(line) {
+                Ok(query) => {
+                    let work = budget.start();
+                    match query.run_within(sifter, &work, quoting) {
+                        Ok(results) => results.iter().for_each(|result| print(&mut stdout, result)),
+                        Err(err) => eprintln!("{}", err),
+                    }
+                    if work.is_partial() {
+                        eprintln!("{}", CUT_SHORT);
+                    }
+                },
+                Err(err) => eprintln!("{}", err.render(line)),
+            },
+        }
     }
 }
 
+/// How many piped lines to run at once. Each chunk's results are printed
+/// before the next starts, so output keeps flowing and a closed pipe stops
+/// the work early.
+const LINES_PER_CHUNK: usize = 256;
+
 fn print(out: &mut io::Stdout, message: &str) {
     if let Err(e) = writeln!(out, "{}", message) {
         match e.kind() {
@@ -64,26 +257,84 @@ fn print(out: &mut io::Stdout, message: &str) {
     }
 }
 
-fn run(sifter: &Sifter, command: SiftCommand) {
+/// Runs the command once, or on each piped line, until the work's budget
+/// runs out.
+fn run(sifter: &Sifter, command: SiftCommand, output_mode: OutputMode, quoting: Quoting, work: &Work) {
     let mut stdout = io::stdout();
     let being_piped_to = !atty::is(Stream::Stdin);
+    let run_once = |command: &SiftCommand| {
+        let mut results = command.run_within(sifter, work);
+        work.limit_results(&mut results);
+        results
+    };
 
     if being_piped_to {
         let mut input = String::new();
         stdin().read_to_string(&mut input).unwrap();
-        for word in input.lines() {
-            let subbed_command = command.substitute(word);
-            for result in subbed_command.run(sifter) {
-                print(&mut stdout, &format!("{} => {}", word, result));
+        if command.takes_all_inputs() {
+            let inputs: Vec<&str> = input.lines().collect();
+            for message in single_run_lines(output_mode, &run_once(&command.gather(&inputs))) {
+                print(&mut stdout, &message);
+            }
+            return;
+        }
+        let mut output = PipedOutput::new(output_mode);
+        let lines: Vec<&str> = input.lines().collect();
+        for chunk in lines.chunks(LINES_PER_CHUNK) {
+            if !work.has_time() {
+                break;
             }
+            let chunk_results = sift::parallel::map(chunk, |line| {
+                command.substitute(&input_stages(line), quoting).map(|command| run_once(&command))
+            });
+            for (line, results) in chunk.iter().zip(chunk_results) {
+                let results = match results {
+                    Ok(results) => results,
+                    Err(err) => {
+                        eprintln!("{}: {:?}", line, err);
+                        continue;
+                    },
+                };
+                for message in output.add(line, &results) {
+                    print(&mut stdout, &message);
+                }
+            }
+        }
+        for message in output.finish() {
+            print(&mut stdout, &message);
         }
     } else {
-        for word in command.run(sifter) {
-            print(&mut stdout, word);
+        for message in single_run_lines(output_mode, &run_once(&command)) {
+            print(&mut stdout, &message);
         }
     }
 }
 
+fn get_budget(matches: &ArgMatches) -> Result<Budget, SiftError> {
+    let get = |name| matches.value_of(name)
+        .map(|value| value.parse::<usize>().map_err(|_| SiftError::InvalidNumber))
+        .transpose();
+    let timeout = matches.value_of("timeout")
+        .map(|value| match value.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(Duration::from_secs_f64(seconds)),
+            _ => Err(SiftError::InvalidNumber),
+        })
+        .transpose()?;
+    Ok(Budget { max_patterns: get("max-patterns")?, max_results: get("max-results")?, timeout })
+}
+
+fn get_serve_options(matches: &ArgMatches) -> Result<ServeOptions, SiftError> {
+    let get = |name| matches.value_of(name).unwrap().parse::<usize>().map_err(|_| SiftError::InvalidNumber);
+    Ok(ServeOptions {
+        max_query_len: get("max-query-len")?,
+        max_results: get("max-results")?,
+        max_running: get("max-running")?,
+        max_connections: get("max-connections")?,
+        timeout: Duration::from_secs(get("timeout")? as u64),
+        ..ServeOptions::default()
+    })
+}
+
 fn load_sifter(matches: &ArgMatches) -> Result<Sifter, SiftError> {
     if let Some(path) = matches.value_of("cache") {
         Sifter::new_from_cache_file(path)
@@ -0,0 +1,210 @@
+use crate::sift_command::SiftMatch;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// How to print the results of a command run once per piped input line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputMode {
+    /// `input => result`, one line per result.
+    Pairs,
+    /// Each distinct result on its own, for piping into another command.
+    Results,
+    /// `input => result, result`, one line per input.
+    Grouped,
+    /// `result <= input, input`, one line per distinct result.
+    Inverted,
+    /// `input => count`, one line per input, or just the count of results
+    /// when there's only one input.
+    Count,
+    /// The total number of results and how many there were of each length.
+    Stats,
+}
+
+impl OutputMode {
+    pub fn from_name(name: &str) -> Option<OutputMode> {
+        match name {
+            "pairs" => Some(OutputMode::Pairs),
+            "results" => Some(OutputMode::Results),
+            "grouped" => Some(OutputMode::Grouped),
+            "inverted" => Some(OutputMode::Inverted),
+            _ => None,
+        }
+    }
+}
+
+/// The word in one field of a piped line, without any `: detail` a result
+/// was printed with.
+fn field_word(field: &str) -> &str {
+    field.split(": ").next().unwrap_or(field).trim()
+}
+
+/// The word to use from a line of piped input: the text after the last
+/// "=>", so sift's own `input => result` lines can be piped straight back in.
+pub fn input_word(line: &str) -> &str {
+    field_word(line.rsplit("=>").next().unwrap_or(line))
+}
+
+/// The word from each stage of the pipeline that produced a line of piped
+/// input, first to last, for filling in `%{1}`, `%{2}`...
+pub fn input_stages(line: &str) -> Vec<&str> {
+    line.split("=>").map(field_word).collect()
+}
+
+/// Lines to print for the results of a command run only once.
+pub fn single_run_lines(mode: OutputMode, results: &[SiftMatch]) -> Vec<String> {
+    match mode {
+        OutputMode::Count => vec![results.len().to_string()],
+        OutputMode::Stats => {
+            let mut tally = Tally::default();
+            tally.add(results);
+            tally.stats()
+        },
+        _ => results.iter().map(|result| result.to_string()).collect(),
+    }
+}
+
+/// Formats results as each input's are added, holding back whatever can only
+/// be printed once every input has been seen.
+pub struct PipedOutput {
+    mode: OutputMode,
+    seen: HashSet<String>,
+    inverted: Vec<(String, Vec<String>)>,
+    inverted_idx: HashMap<String, usize>,
+    tally: Tally,
+}
+
+impl PipedOutput {
+    pub fn new(mode: OutputMode) -> PipedOutput {
+        PipedOutput { mode, seen: HashSet::new(), inverted: Vec::new(), inverted_idx: HashMap::new(), tally: Tally::default() }
+    }
+
+    /// Lines to print for the results of one input line.
+    pub fn add(&mut self, line: &str, results: &[SiftMatch]) -> Vec<String> {
+        match self.mode {
+            OutputMode::Pairs => results.iter().map(|result| format!("{} => {}", line, result)).collect(),
+            OutputMode::Results => results.iter()
+                .map(|result| result.to_string())
+                .filter(|result| self.seen.insert(result.clone()))
+                .collect(),
+            OutputMode::Grouped if results.is_empty() => vec![],
+            OutputMode::Grouped => {
+                let results: Vec<String> = results.iter().map(|result| result.to_string()).collect();
+                vec![format!("{} => {}", input_word(line), results.join(", "))]
+            },
+            OutputMode::Inverted => {
+                let input = input_word(line).to_string();
+                for result in results {
+                    let result = result.to_string();
+                    let inverted = &mut self.inverted;
+                    let idx = *self.inverted_idx.entry(result.clone()).or_insert_with(|| {
+                        inverted.push((result, Vec::new()));
+                        inverted.len() - 1
+                    });
+                    let inputs = &mut self.inverted[idx].1;
+                    if !inputs.contains(&input) {
+                        inputs.push(input.clone());
+                    }
+                }
+                vec![]
+            },
+            OutputMode::Count => vec![format!("{} => {}", input_word(line), self.tally.add(results))],
+            OutputMode::Stats => {
+                self.tally.add(results);
+                vec![]
+            },
+        }
+    }
+
+    /// Lines to print once every input has been added.
+    pub fn finish(self) -> Vec<String> {
+        if self.mode == OutputMode::Stats {
+            return self.tally.stats();
+        }
+        self.inverted.into_iter()
+            .map(|(result, inputs)| format!("{} <= {}", result, inputs.join(", ")))
+            .collect()
+    }
+}
+
+/// Counts of results, for printing instead of the results themselves.
+#[derive(Debug, Default)]
+pub struct Tally {
+    total: usize,
+    inputs: usize,
+    /// How many results have each number of letters.
+    by_length: BTreeMap<usize, usize>,
+}
+
+impl Tally {
+    /// Counts one input's results, returning how many there were.
+    pub fn add(&mut self, results: &[SiftMatch]) -> usize {
+        self.total += results.len();
+        self.inputs += 1;
+        for result in results {
+            let len = result.word.chars().filter(|c| c.is_alphabetic()).count();
+            *self.by_length.entry(len).or_insert(0) += 1;
+        }
+        results.len()
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The total, how many inputs there were if there was more than one, and
+    /// how many results there were of each length.
+    pub fn stats(&self) -> Vec<String> {
+        let mut lines = vec![format!("total: {}", self.total)];
+        if self.inputs > 1 {
+            lines.push(format!("inputs: {}", self.inputs));
+        }
+        lines.extend(self.by_length.iter().map(|(len, count)| format!("length {}: {}", len, count)));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mode: OutputMode) -> Vec<String> {
+        let mut output = PipedOutput::new(mode);
+        let mut lines = Vec::new();
+        lines.extend(output.add("hose", &[SiftMatch::from("shoe"), SiftMatch::from("hoes")]));
+        lines.extend(output.add("x => shoe", &[SiftMatch::from("hose"), SiftMatch::from("hoes")]));
+        lines.extend(output.add("mall", &[]));
+        lines.extend(output.finish());
+        lines
+    }
+
+    #[test]
+    fn test_input_word() {
+        assert_eq!(input_word("hose"), "hose");
+        assert_eq!(input_word("a => b => hose shoe"), "hose shoe");
+        assert_eq!(input_stages("hose"), vec!["hose"]);
+        assert_eq!(input_stages("a => b => hose shoe"), vec!["a", "b", "hose shoe"]);
+        assert_eq!(input_word("hose => hose: 47"), "hose");
+        assert_eq!(input_stages("hose: 47 => shoe: pangram"), vec!["hose", "shoe"]);
+    }
+
+    #[test]
+    fn test_modes() {
+        assert_eq!(run(OutputMode::Pairs), vec!["hose => shoe", "hose => hoes", "x => shoe => hose", "x => shoe => hoes"]);
+        assert_eq!(run(OutputMode::Results), vec!["shoe", "hoes", "hose"]);
+        assert_eq!(run(OutputMode::Grouped), vec!["hose => shoe, hoes", "shoe => hose, hoes"]);
+        assert_eq!(run(OutputMode::Inverted), vec!["shoe <= hose", "hoes <= hose, shoe", "hose <= shoe"]);
+        assert_eq!(OutputMode::from_name("grouped"), Some(OutputMode::Grouped));
+        assert_eq!(run(OutputMode::Count), vec!["hose => 2", "shoe => 2", "mall => 0"]);
+        assert_eq!(run(OutputMode::Stats), vec!["total: 4", "inputs: 3", "length 4: 4"]);
+        assert_eq!(single_run_lines(OutputMode::Count, &[SiftMatch::from("shoe")]), vec!["1"]);
+        assert_eq!(OutputMode::from_name("nope"), None);
+    }
+
+    #[test]
+    fn test_tally() {
+        let mut tally = Tally::default();
+        assert_eq!(tally.add(&[SiftMatch::from("shoe"), SiftMatch::from("hoes"), SiftMatch::from("hose shoe")]), 3);
+        assert_eq!(tally.add(&[]), 0);
+        assert_eq!(tally.total(), 3);
+        assert_eq!(tally.stats(), vec!["total: 3", "inputs: 2", "length 4: 2", "length 8: 1"]);
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+/// Maps over the items across all cores, keeping the results in the items'
+/// order.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+    where T: Sync, U: Send, F: Fn(&T) -> U + Sync + Send {
+    items.par_iter().map(f).collect()
+}
+
+/// Maps over the items in order. There are no threads in wasm, so this runs
+/// in sequence.
+#[cfg(target_arch = "wasm32")]
+pub fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+    where T: Sync, U: Send, F: Fn(&T) -> U + Sync + Send {
+    items.iter().map(f).collect()
+}
+
+/// Like `map`, but concatenates the results.
+pub fn flat_map<T, U, I, F>(items: &[T], f: F) -> Vec<U>
+    where T: Sync, I: IntoIterator<Item = U> + Send, F: Fn(&T) -> I + Sync + Send {
+    map(items, f).into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_keeps_order() {
+        let items: Vec<usize> = (0..1000).collect();
+        assert_eq!(map(&items, |n| n * 2), items.iter().map(|n| n * 2).collect::<Vec<_>>());
+        assert_eq!(flat_map(&[1, 2, 3], |&n| vec![n; n]), vec![1, 2, 2, 3, 3, 3]);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::error::SiftError;
+use crate::argparse::parse_tokens;
+use crate::lexer::{ParseError, Span, Token, TokenKind, tokenize};
+use crate::sift_command::{SetOp, SiftCommand};
+use crate::sifter::Sifter;
+use crate::template::Quoting;
+use crate::parallel;
+use crate::budget::Work;
+
+/// A query in the grammar shared by the command line, the REPL and the web
+/// page:
+///
+/// ```text
+/// query   := set ('|' set)*
+/// set     := operand (('&' | '+' | '-') operand)*
+/// operand := '(' set ')' | command
+/// ```
+///
+/// Each stage after a '|' runs on every result of the one before it, and
+/// set operators, which must be followed by a command's name (or a word
+/// starting with a letter) or '(', combine results left to right.
+#[derive(Debug)]
+pub struct Query {
+    stages: Vec<SiftCommand>,
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Where the query ends, for errors about something missing.
+    fn end(&self) -> Span {
+        let end = self.tokens.last().map_or(0, |token| token.span.end);
+        Span::new(end, end)
+    }
+
+    /// The set operator at `idx`, if the word there is one.
+    fn set_op_at(&self, idx: usize) -> Option<SetOp> {
+        let op = SetOp::from_token(self.tokens.get(idx)?.bare_word()?)?;
+        match &self.tokens.get(idx + 1)?.kind {
+            TokenKind::Open => Some(op),
+            TokenKind::Word { text, .. } if text.starts_with(|c: char| c.is_ascii_alphabetic()) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn parse_query(&mut self) -> Result<Vec<SiftCommand>, ParseError> {
+        let mut stages = vec![self.parse_set()?];
+        while let Some(token) = self.peek() {
+            match token.kind {
+                TokenKind::Pipe => {
+                    self.pos += 1;
+                    stages.push(self.parse_set()?);
+                },
+                TokenKind::Close => return Err(ParseError::new("unmatched ')'", token.span)),
+                _ => return Err(ParseError::new("expected '|' or a set operator", token.span)),
+            }
+        }
+        Ok(stages)
+    }
+
+    /// Combined results are sorted the way the first operand's were.
+    fn parse_set(&mut self) -> Result<SiftCommand, ParseError> {
+        let first = self.parse_operand()?;
+        let order = match &first {
+            SiftCommand::Sort(_, order) => Some(*order),
+            _ => None,
+        };
+        let mut combined = first;
+        let mut combining = false;
+        while let Some(op) = self.set_op_at(self.pos) {
+            self.pos += 1;
+            combined = SiftCommand::Combine(op, Box::new(combined), Box::new(self.parse_operand()?));
+            combining = true;
+        }
+        Ok(match order {
+            Some(order) if combining => SiftCommand::Sort(Box::new(combined), order),
+            _ => combined,
+        })
+    }
+
+    fn parse_operand(&mut self) -> Result<SiftCommand, ParseError> {
+        let token = self.peek().ok_or_else(|| ParseError::new("expected a command", self.end()))?;
+        match token.kind {
+            TokenKind::Open => {
+                self.pos += 1;
+                let inner = self.parse_set()?;
+                match self.peek() {
+                    Some(Token { kind: TokenKind::Close, .. }) => {
+                        self.pos += 1;
+                        Ok(inner)
+                    },
+                    _ => Err(ParseError::new("unclosed '('", token.span)),
+                }
+            },
+            TokenKind::Word { .. } => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(Token { kind: TokenKind::Word { .. }, .. })) && self.set_op_at(self.pos).is_none() {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(ParseError::new("expected a command before the operator", token.span));
+                }
+                parse_tokens(&self.tokens[start..self.pos])
+            },
+            TokenKind::Pipe => Err(ParseError::new("expected a command before '|'", token.span)),
+            TokenKind::Close => Err(ParseError::new("expected a command before ')'", token.span)),
+        }
+    }
+}
+
+/// Each line of a pipeline's output so far: the word from each stage, to
+/// fill in placeholders, and the line as it's printed.
+struct Chain {
+    words: Vec<String>,
+    line: String,
+}
+
+impl Chain {
+    fn words(&self) -> Vec<&str> {
+        self.words.iter().map(String::as_str).collect()
+    }
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query, ParseError> {
+        Query::from_tokens(&tokenize(query)?)
+    }
+
+    /// A query from tokens, such as `lexer::tokens_from_args` makes of the
+    /// command line.
+    pub fn from_tokens(tokens: &[Token]) -> Result<Query, ParseError> {
+        let stages = Parser { tokens, pos: 0 }.parse_query()?;
+        Ok(Query { stages })
+    }
+
+    pub fn stages(&self) -> &[SiftCommand] {
+        &self.stages
+    }
+
+    pub fn into_stages(self) -> Vec<SiftCommand> {
+        self.stages
+    }
+
+    /// Runs one stage on the chains the stage before it made, skipping the
+    /// rest once the work runs out of time. A stage that takes all its inputs
+    /// at once starts the chains afresh.
+    fn step(stage: &SiftCommand, chains: Vec<Chain>, sifter: &Sifter, work: &Work, quoting: Quoting) -> Result<Vec<Chain>, SiftError> {
+        if stage.takes_all_inputs() {
+            let inputs: Vec<&str> = chains.iter().map(|chain| chain.words.last().unwrap().as_str()).collect();
+            return Ok(stage.gather(&inputs).run_within(sifter, work).iter()
+                .map(|m| Chain { words: vec![m.word.to_string()], line: m.to_string() })
+                .collect());
+        }
+        let results = parallel::map(&chains, |chain| {
+            if !work.has_time() {
+                return Ok(Vec::new());
+            }
+            let stage = stage.substitute(&chain.words(), quoting)?;
+            Ok(stage.run_within(sifter, work).iter()
+                .map(|m| {
+                    let mut words = chain.words.clone();
+                    words.push(m.word.to_string());
+                    Chain { words, line: format!("{} => {}", chain.line, m) }
+                })
+                .collect())
+        });
+        Ok(results.into_iter().collect::<Result<Vec<Vec<Chain>>, SiftError>>()?.into_iter().flatten().collect())
+    }
+
+    fn check(&self, sifter: &Sifter) -> Result<(), SiftError> {
+        self.stages.iter().try_for_each(|stage| stage.check(sifter))
+    }
+
+    /// The chains made by every stage but the last.
+    fn run_leading(&self, sifter: &Sifter, work: &Work, quoting: Quoting) -> Result<Option<Vec<Chain>>, SiftError> {
+        let (first, rest) = self.stages.split_first().unwrap();
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        let chains = first.run_within(sifter, work).iter()
+            .map(|m| Chain { words: vec![m.word.to_string()], line: m.to_string() })
+            .collect();
+        rest[..rest.len() - 1].iter().try_fold(chains, |chains, stage| Query::step(stage, chains, sifter, work, quoting)).map(Some)
+    }
+
+    /// The results as they're printed, with `input => result` for each
+    /// result of a pipe. Piped words match literally in regexes.
+    pub fn run(&self, sifter: &Sifter) -> Result<Vec<String>, SiftError> {
+        self.run_within(sifter, &Work::unlimited(), Quoting::Literal)
+    }
+
+    /// Like `run`, but stops early once the work's budget is used up, keeping
+    /// the results so far, and fills piped words into regexes with `quoting`.
+    pub fn run_within(&self, sifter: &Sifter, work: &Work, quoting: Quoting) -> Result<Vec<String>, SiftError> {
+        self.check(sifter)?;
+        let last = self.stages.last().unwrap();
+        let mut results: Vec<String> = match self.run_leading(sifter, work, quoting)? {
+            None => last.run_within(sifter, work).iter().map(|m| m.to_string()).collect(),
+            Some(chains) => Query::step(last, chains, sifter, work, quoting)?.into_iter().map(|chain| chain.line).collect(),
+        };
+        work.limit_results(&mut results);
+        Ok(results)
+    }
+
+    /// How many results there are, without formatting them.
+    pub fn count(&self, sifter: &Sifter) -> Result<usize, SiftError> {
+        self.count_within(sifter, &Work::unlimited(), Quoting::Literal)
+    }
+
+    /// Like `count`, but stops early once the work's budget is used up, and
+    /// fills piped words into regexes with `quoting`.
+    pub fn count_within(&self, sifter: &Sifter, work: &Work, quoting: Quoting) -> Result<usize, SiftError> {
+        self.check(sifter)?;
+        let last = self.stages.last().unwrap();
+        let chains = match self.run_leading(sifter, work, quoting)? {
+            None => return Ok(last.run_within(sifter, work).len()),
+            Some(chains) => chains,
+        };
+        if last.takes_all_inputs() {
+            return Ok(Query::step(last, chains, sifter, work, quoting)?.len());
+        }
+        let counts = parallel::map(&chains, |chain| match work.has_time() {
+            true => Ok(last.substitute(&chain.words(), quoting)?.run_within(sifter, work).len()),
+            false => Ok(0),
+        });
+        counts.into_iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokens_from_args;
+    use crate::sift_command::SortOrder;
+
+    fn sifter(words: &[&str]) -> Sifter {
+        Sifter::new_from_words(words.join("\n").as_bytes())
+    }
+
+    fn error_at(query: &str) -> (String, Span) {
+        let err = Query::parse(query).unwrap_err();
+        (err.message, err.span)
+    }
+
+    fn run(query: &str) -> Vec<String> {
+        let sifter = sifter(&["hose", "shoe", "hoes", "horse", "shores", "rose"]);
+        Query::parse(query).unwrap().run(&sifter).unwrap()
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let stages = Query::parse("anagram horse + bank hose - h.se").unwrap().into_stages();
+        match &stages[..] {
+            [SiftCommand::Combine(SetOp::Difference, left, _)] => {
+                assert!(matches!(**left, SiftCommand::Combine(SetOp::Union, _, _)));
+            },
+            _ => panic!("expected a difference"),
+        }
+        let stages = Query::parse("anagram horse - (bank hose + h.se)").unwrap().into_stages();
+        assert!(matches!(&stages[..], [SiftCommand::Combine(SetOp::Difference, _, _)]));
+        let stages = Query::parse("--sort freq anagram horse + rose").unwrap().into_stages();
+        assert!(matches!(&stages[..], [SiftCommand::Sort(command, SortOrder::Freq)] if matches!(**command, SiftCommand::Combine(SetOp::Union, _, _))));
+
+        // A '-' followed by something other than a letter is an argument.
+        let stages = Query::from_tokens(&tokens_from_args(&["decode", "morse", "-", "...", "+", "---"]).1).unwrap().into_stages();
+        assert!(matches!(&stages[..], [SiftCommand::Decode(_, message, false, _)] if message == "- ... + ---"));
+        assert_eq!(error_at("anagram x + bogus x").0, "unexpected argument 'x' for <regex>");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(error_at(""), ("expected a command".to_string(), Span::new(0, 0)));
+        assert_eq!(error_at("anagram horse |"), ("expected a command".to_string(), Span::new(15, 15)));
+        assert_eq!(error_at("| bank x"), ("expected a command before '|'".to_string(), Span::new(0, 1)));
+        assert_eq!(error_at("(anagram horse"), ("unclosed '('".to_string(), Span::new(0, 1)));
+        assert_eq!(error_at("anagram horse)"), ("unmatched ')'".to_string(), Span::new(13, 14)));
+        assert_eq!(error_at("anagram horse (bank x)"), ("expected '|' or a set operator".to_string(), Span::new(14, 15)));
+        assert_eq!(error_at("anagram 'horse").1, Span::new(8, 14));
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(run("anagram hose"), vec!["hoes", "shoe"]);
+        assert_eq!(run("'horse' | delete %"), vec!["horse => hose"]);
+        assert_eq!(run("bank hose | anagram % | delete -n 0 %{1}"), vec![
+            "hoes => hose => hoes", "hoes => shoe => hoes", "hose => hoes => hose",
+            "hose => shoe => hose", "shoe => hoes => shoe", "shoe => hose => shoe",
+        ]);
+        assert_eq!(run("(anagram hose + rose) - shoe"), vec!["hoes", "rose"]);
+        let sifter = sifter(&["hose", "shoe", "hoes"]);
+        assert_eq!(Query::parse("anagram hose | anagram %").unwrap().count(&sifter).unwrap(), 4);
+        let query = Query::parse("encode morse i | 'ho%'").unwrap();
+        assert_eq!(query.run(&sifter).unwrap(), Vec::<String>::new());
+        assert_eq!(query.run_within(&sifter, &Work::unlimited(), Quoting::Raw).unwrap(), vec![".. => hoes", ".. => hose"]);
+        assert_eq!(query.count_within(&sifter, &Work::unlimited(), Quoting::Raw).unwrap(), 2);
+        let query = Query::parse("bank hose | acrostic --target hoxe").unwrap();
+        assert!(matches!(query.run(&sifter), Err(SiftError::UnknownWord)));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::error::SiftError;
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::io::BufReader;
+
+const SCRABBLE_VALUES: [u32; 26] = [
+    1, 3, 3, 2, 1, 4, 2, 4, 1, 8, 5, 1, 3, 1, 1, 3, 10, 1, 1, 1, 1, 4, 4, 8, 4, 10,
+];
+
+/// Sequential gematria: a-i count 1-9, j-r count 10-90, s-z count 100-800.
+const GEMATRIA_VALUES: [u32; 26] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9,
+    10, 20, 30, 40, 50, 60, 70, 80, 90,
+    100, 200, 300, 400, 500, 600, 700, 800,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScoreScheme {
+    A1Z26,
+    Scrabble,
+    Gematria,
+    Custom(HashMap<char, u32>),
+}
+
+impl ScoreScheme {
+    pub fn letter_value(&self, letter: char) -> u32 {
+        let letter = letter.to_ascii_lowercase();
+        let idx = match letter {
+            'a'..='z' => letter as usize - 'a' as usize,
+            _ => return 0,
+        };
+        match self {
+            ScoreScheme::A1Z26 => idx as u32 + 1,
+            ScoreScheme::Scrabble => SCRABBLE_VALUES[idx],
+            ScoreScheme::Gematria => GEMATRIA_VALUES[idx],
+            ScoreScheme::Custom(mapping) => mapping.get(&letter).cloned().unwrap_or(0),
+        }
+    }
+
+    pub fn score(&self, word: &str) -> u32 {
+        word.chars().map(|letter| self.letter_value(letter)).sum()
+    }
+}
+
+/// Reads a custom letter mapping, one `letter value` pair per line. The pair
+/// may also be separated by '=', ':' or ','. Blank lines and lines starting
+/// with '#' are ignored, and letters missing from the mapping score 0.
+pub fn read_mapping<R>(data: R) -> Result<HashMap<char, u32>, SiftError> where R: Read {
+    let mut mapping = HashMap::new();
+    for line in BufReader::new(data).lines() {
+        let line = line.map_err(|_| SiftError::FileIOError)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(|c: char| c.is_whitespace() || "=:,".contains(c))
+            .filter(|field| !field.is_empty());
+        let letter = fields.next()
+            .and_then(|field| {
+                let mut chars = field.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(letter), None) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_lowercase()),
+                    _ => None,
+                }
+            })
+            .ok_or(SiftError::InvalidMapping)?;
+        let value = fields.next()
+            .and_then(|field| str::parse::<u32>(field).ok())
+            .ok_or(SiftError::InvalidMapping)?;
+        mapping.insert(letter, value);
+    }
+    Ok(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemes() {
+        assert_eq!(ScoreScheme::A1Z26.score("hose"), 47);
+        assert_eq!(ScoreScheme::Scrabble.score("hose"), 7);
+        assert_eq!(ScoreScheme::Scrabble.score("quiz"), 22);
+        assert_eq!(ScoreScheme::Gematria.score("hose"), 173);
+        assert_eq!(ScoreScheme::A1Z26.score("HoSe"), 47);
+    }
+
+    #[test]
+    fn test_read_mapping() {
+        let mapping = read_mapping("a 5\n# vowels are cheap\n\nb=7\nC: 2\n".as_bytes()).unwrap();
+        let scheme = ScoreScheme::Custom(mapping);
+        assert_eq!(scheme.score("abcd"), 14);
+        assert!(read_mapping("ab 5".as_bytes()).is_err());
+        assert!(read_mapping("a five".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::trie::TrieNode;
+use std::collections::HashSet;
+
+/// How many letters may be skipped between two consecutive letters of a
+/// subsequence. Letters before the first and after the last are unconstrained.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Gaps {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Gaps {
+    pub fn allows(&self, gap: usize) -> bool {
+        gap >= self.min && self.max.is_none_or(|max| gap <= max)
+    }
+}
+
+/// Collects words spelled by picking letters, in order, out of `letters`.
+/// `ends` holds the positions in `letters` the path so far could have taken
+/// its last letter from, and is empty at the root.
+pub fn walk_subsequences(node: &TrieNode, letters: &[char], gaps: &Gaps, ends: &[usize], results: &mut HashSet<usize>) {
+    for (letter, child) in node.children() {
+        let next_ends: Vec<usize> = (0..letters.len())
+            .filter(|&pos| letters[pos] == letter)
+            .filter(|&pos| ends.is_empty() || ends.iter().any(|&end| pos > end && gaps.allows(pos - end - 1)))
+            .collect();
+        if next_ends.is_empty() {
+            continue;
+        }
+        results.extend(child.words());
+        walk_subsequences(child, letters, gaps, &next_ends, results);
+    }
+}
+
+/// Collects words containing `letters` as a subsequence. Each state is how
+/// many of `letters` the path so far has matched, and how many letters it has
+/// skipped since the last match.
+pub fn walk_supersequences(node: &TrieNode, letters: &[char], gaps: &Gaps, states: &[(usize, usize)], results: &mut HashSet<usize>) {
+    if states.iter().any(|&(matched, _)| matched == letters.len()) {
+        results.extend(node.words());
+    }
+    for (letter, child) in node.children() {
+        let mut next_states = Vec::new();
+        for &(matched, gap) in states {
+            let started = matched > 0;
+            let finished = matched == letters.len();
+            if !finished && letters[matched] == letter && (!started || gaps.allows(gap)) {
+                next_states.push((matched + 1, 0));
+            }
+            if !started || finished {
+                next_states.push((matched, 0));
+            } else if gaps.max.is_none_or(|max| gap < max) {
+                next_states.push((matched, gap + 1));
+            }
+        }
+        next_states.sort_unstable();
+        next_states.dedup();
+        if !next_states.is_empty() {
+            walk_supersequences(child, letters, gaps, &next_states, results);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps() {
+        let gaps = Gaps { min: 1, max: Some(2) };
+        assert!(!gaps.allows(0));
+        assert!(gaps.allows(2));
+        assert!(!gaps.allows(3));
+        assert!(Gaps::default().allows(100));
+    }
+}
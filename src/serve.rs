@@ -0,0 +1,432 @@
+use sift::query::Query;
+use sift::template::Quoting;
+use sift::{Budget, Sifter};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits on what a single request may ask of the server.
+#[derive(Clone, Copy, Debug)]
+pub struct ServeOptions {
+    /// Longest query accepted, in bytes.
+    pub max_query_len: usize,
+    /// Most results sent back for one query. The count still covers them all.
+    pub max_results: usize,
+    /// Most queries running at once. Queries that time out stop at their
+    /// next check of the time, and count against this until they do.
+    pub max_running: usize,
+    /// How long to wait for a query's results.
+    pub timeout: Duration,
+    /// Most patterns any one command may try.
+    pub max_patterns: Option<usize>,
+    /// Most connections handled at once. As many again wait their turn, and
+    /// any more wait to be accepted.
+    pub max_connections: usize,
+}
+
+impl Default for ServeOptions {
+    fn default() -> ServeOptions {
+        ServeOptions {
+            max_query_len: 1000,
+            max_results: 1000,
+            max_running: 8,
+            timeout: Duration::from_secs(10),
+            max_patterns: None,
+            max_connections: 32,
+        }
+    }
+}
+
+/// Longest request line or header accepted, on top of the query itself.
+const MAX_HEADER_LEN: usize = 8192;
+/// How long to wait on a client that has stopped sending its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long past its deadline a query has to hand back what it found before
+/// it's given up on.
+const GRACE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    query: String,
+    results: Vec<String>,
+    count: usize,
+    /// Whether there were more results than were sent back.
+    truncated: bool,
+    /// Whether the query stopped early at a limit on its work, so the results
+    /// and count may be missing some.
+    partial: bool,
+    error: Option<String>,
+    elapsed_ms: f64,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    params: HashMap<String, String>,
+    body: String,
+}
+
+/// Decodes `%XX` escapes, and '+' as a space, from part of a URL.
+fn url_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes.get(idx + 1..idx + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        idx += 2;
+                    },
+                    None => decoded.push(b'%'),
+                }
+            },
+            byte => decoded.push(byte),
+        }
+        idx += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_params(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| match param.split_once('=') {
+            Some((key, value)) => (url_decode(key), url_decode(value)),
+            None => (url_decode(param), String::new()),
+        })
+        .collect()
+}
+
+/// Why a request couldn't be read: the status to answer with, and a message.
+type Refusal = (&'static str, String);
+
+const BAD_REQUEST: &str = "400 Bad Request";
+const TOO_LARGE: &str = "413 Payload Too Large";
+const UNAVAILABLE: &str = "503 Service Unavailable";
+
+fn malformed<E: ToString>(err: E) -> Refusal {
+    (BAD_REQUEST, err.to_string())
+}
+
+/// Reads one line of the request head, refusing to buffer more than `limit`.
+fn read_line<R: BufRead>(reader: &mut R, limit: usize) -> Result<String, Refusal> {
+    let mut line = String::new();
+    reader.take(limit as u64 + 1).read_line(&mut line).map_err(malformed)?;
+    if line.len() > limit {
+        return Err((TOO_LARGE, "request too long".to_string()));
+    }
+    Ok(line.trim_end().to_string())
+}
+
+fn read_request<R: BufRead>(reader: &mut R, options: &ServeOptions) -> Result<Request, Refusal> {
+    let invalid = || malformed("malformed request");
+    let limit = MAX_HEADER_LEN + options.max_query_len * 3;
+    let request_line = read_line(reader, limit)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or_else(invalid)?.to_string();
+    let target = parts.next().ok_or_else(invalid)?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    loop {
+        let header = read_line(reader, MAX_HEADER_LEN)?;
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().map_err(|_| invalid())?;
+            }
+        }
+    }
+    if content_length > options.max_query_len {
+        return Err((TOO_LARGE, "query too long".to_string()));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(malformed)?;
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        params: parse_params(query),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, response: &Response) -> io::Result<()> {
+    let body = serde_json::to_string(response).map_err(io::Error::other)?;
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body)?;
+    stream.flush()
+}
+
+/// Decrements the running query count when a query finishes, however it ends.
+struct RunningGuard(Arc<AtomicUsize>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Runs the job on its own thread, giving up on it after the timeout. A job
+/// given up on keeps running, so it should be bounded by the timeout too.
+fn run_with_timeout<T, F>(timeout: Duration, job: F) -> Option<T>
+    where T: Send + 'static, F: FnOnce() -> T + Send + 'static
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone if the job timed out.
+        let _ = sender.send(job());
+    });
+    receiver.recv_timeout(timeout).ok()
+}
+
+/// Runs a query with the server's limits, returning the HTTP status to answer
+/// with.
+fn answer(request: &Request, sifter: &Arc<Sifter>, options: &ServeOptions, running: &Arc<AtomicUsize>, response: &mut Response) -> &'static str {
+    let count_only = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/sift") | ("POST", "/sift") => false,
+        ("GET", "/count") | ("POST", "/count") => true,
+        (_, "/sift") | (_, "/count") => {
+            response.error = Some("method not allowed".to_string());
+            return "405 Method Not Allowed";
+        },
+        _ => {
+            response.error = Some("not found".to_string());
+            return "404 Not Found";
+        },
+    };
+    response.query = match request.method.as_str() {
+        "POST" => request.body.trim().to_string(),
+        _ => request.params.get("q").cloned().unwrap_or_default(),
+    };
+    if response.query.len() > options.max_query_len {
+        response.error = Some("query too long".to_string());
+        return TOO_LARGE;
+    }
+    let limit = match request.params.get("limit").map(|limit| limit.parse::<usize>()) {
+        Some(Ok(limit)) => limit.min(options.max_results),
+        Some(Err(_)) => {
+            response.error = Some("invalid limit".to_string());
+            return BAD_REQUEST;
+        },
+        None => options.max_results,
+    };
+    let quoting = match request.params.get("raw").map(String::as_str) {
+        None | Some("0") | Some("false") => Quoting::Literal,
+        Some("") | Some("1") | Some("true") => Quoting::Raw,
+        Some(_) => {
+            response.error = Some("invalid raw".to_string());
+            return BAD_REQUEST;
+        },
+    };
+    let query = match Query::parse(&response.query) {
+        Ok(query) => query,
+        Err(err) => {
+            response.error = Some(err.to_string());
+            return BAD_REQUEST;
+        },
+    };
+
+    if running.fetch_add(1, Ordering::SeqCst) >= options.max_running {
+        running.fetch_sub(1, Ordering::SeqCst);
+        response.error = Some("too many queries running".to_string());
+        return UNAVAILABLE;
+    }
+    let guard = RunningGuard(Arc::clone(running));
+    let sifter = Arc::clone(sifter);
+    let work = Budget { max_patterns: options.max_patterns, max_results: None, timeout: Some(options.timeout) }.start();
+    let results = run_with_timeout(options.timeout + GRACE, move || {
+        let _guard = guard;
+        let results = if count_only {
+            query.count_within(&sifter, &work, quoting).map(|count| (Vec::new(), count))
+        } else {
+            query.run_within(&sifter, &work, quoting).map(|results| {
+                let count = results.len();
+                (results.into_iter().take(limit).collect(), count)
+            })
+        };
+        results.map(|(results, count)| (results, count, work.is_partial()))
+    });
+    match results {
+        Some(Ok((results, count, partial))) => {
+            response.partial = partial;
+            response.truncated = results.len() < count && !count_only;
+            response.results = results;
+            response.count = count;
+            "200 OK"
+        },
+        Some(Err(err)) => {
+            response.error = Some(format!("{:?}", err));
+            BAD_REQUEST
+        },
+        None => {
+            response.error = Some("query timed out".to_string());
+            "504 Gateway Timeout"
+        },
+    }
+}
+
+fn handle(mut stream: TcpStream, sifter: &Arc<Sifter>, options: &ServeOptions, running: &Arc<AtomicUsize>) -> io::Result<()> {
+    let start = Instant::now();
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut response = Response::default();
+    let request = read_request(&mut BufReader::new(&mut stream), options);
+    let status = match request {
+        Ok(request) => answer(&request, sifter, options, running, &mut response),
+        Err((status, message)) => {
+            response.error = Some(message);
+            status
+        },
+    };
+    response.elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    write_response(&mut stream, status, &response)
+}
+
+/// Answers queries on the listener until it fails, with a fixed pool of
+/// threads taking connections from a bounded queue. While the queue is full,
+/// no more connections are accepted.
+pub fn serve_on(listener: TcpListener, sifter: Sifter, options: ServeOptions) -> io::Result<()> {
+    let sifter = Arc::new(sifter);
+    let running = Arc::new(AtomicUsize::new(0));
+    let threads = options.max_connections.max(1);
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(threads);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..threads {
+        let receiver = Arc::clone(&receiver);
+        let sifter = Arc::clone(&sifter);
+        let running = Arc::clone(&running);
+        thread::spawn(move || loop {
+            let stream = match receiver.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            };
+            if let Err(err) = handle(stream, &sifter, &options, &running) {
+                eprintln!("{}", err);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        sender.send(stream?).map_err(|_| io::Error::other("no threads left to answer connections"))?;
+    }
+    Ok(())
+}
+
+/// Answers queries on localhost at the given port.
+pub fn serve(port: u16, sifter: Sifter, options: ServeOptions) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("serving on http://{}", listener.local_addr()?);
+    serve_on(listener, sifter, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &str = "hose\nshoe\nhoes\nrose\nhorse\n";
+
+    fn start_server(options: ServeOptions, words: &str) -> u16 {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sifter = Sifter::new_from_words(words.as_bytes());
+        thread::spawn(move || serve_on(listener, sifter, options));
+        port
+    }
+
+    fn request(port: u16, request: &str) -> (String, serde_json::Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().split_once(' ').unwrap().1.to_string();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode("anagram%20hose+%7C+x%"), "anagram hose | x%");
+        assert_eq!(parse_params("q=h.se&limit=2")["q"], "h.se");
+    }
+
+    #[test]
+    fn test_serve() {
+        let port = start_server(ServeOptions { max_query_len: 20, max_results: 2, ..Default::default() }, WORDS);
+
+        let (status, body) = request(port, "GET /sift?q=anagram+hose HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(status, "200 OK");
+        assert_eq!(body["results"], serde_json::json!(["hoes", "shoe"]));
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["truncated"], false);
+        assert!(body["error"].is_null());
+
+        let (status, body) = request(port, "POST /sift?limit=1 HTTP/1.1\r\nContent-Length: 4\r\n\r\n.o.e");
+        assert_eq!(status, "200 OK");
+        assert_eq!(body["results"], serde_json::json!(["hose"]));
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["truncated"], true);
+
+        let (status, body) = request(port, "GET /count?q=.o.e HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "200 OK");
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["partial"], false);
+
+        let (status, body) = request(port, "GET /sift?q=bogus+x HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "400 Bad Request");
+        assert!(body["error"].is_string());
+
+        let (status, _) = request(port, "GET /sift?q=anagram+abcdefghijklmnopqrstuvwxyz HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "413 Payload Too Large");
+        let (status, _) = request(port, "GET /nope HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "404 Not Found");
+
+        let port = start_server(ServeOptions::default(), WORDS);
+        let (_, body) = request(port, "GET /count?q=encode+morse+i+|+ho%25 HTTP/1.1\r\n\r\n");
+        assert_eq!(body["count"], 0);
+        let (_, body) = request(port, "GET /count?q=encode+morse+i+|+ho%25&raw=1 HTTP/1.1\r\n\r\n");
+        assert_eq!(body["count"], 2);
+        let (status, _) = request(port, "GET /count?q=x&raw=maybe HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "400 Bad Request");
+
+        let port = start_server(ServeOptions { max_patterns: Some(1), ..Default::default() }, WORDS);
+        let (status, body) = request(port, "GET /sift?q=delete+horse HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "200 OK");
+        assert_eq!(body["partial"], true);
+    }
+
+    #[test]
+    fn test_limits() {
+        let port = start_server(ServeOptions { max_running: 0, ..Default::default() }, WORDS);
+        let (status, _) = request(port, "GET /sift?q=anagram+hose HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "503 Service Unavailable");
+
+        // One thread answers each connection in turn.
+        let port = start_server(ServeOptions { max_connections: 1, ..Default::default() }, WORDS);
+        for _ in 0..3 {
+            assert_eq!(request(port, "GET /count?q=.o.e HTTP/1.1\r\n\r\n").0, "200 OK");
+        }
+
+        let (release, blocked) = mpsc::channel::<()>();
+        assert_eq!(run_with_timeout(Duration::from_millis(10), move || blocked.recv().is_ok()), None);
+        release.send(()).unwrap();
+        assert_eq!(run_with_timeout(Duration::from_secs(10), || 1), Some(1));
+
+        // A query stopped at its deadline still answers with what it found.
+        let port = start_server(ServeOptions { timeout: Duration::ZERO, ..Default::default() }, WORDS);
+        let (status, body) = request(port, "GET /sift?q=add+-n+2+hose HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "200 OK");
+        assert_eq!(body["partial"], true);
+    }
+}
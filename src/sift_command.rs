@@ -1,9 +1,144 @@
 use crate::sifter::Sifter;
-use regex::Regex;
+use crate::extract::{phrase_words, Selection, Transform};
+use crate::score::ScoreScheme;
+use crate::keypad;
+use crate::encoding::Encoding;
+use crate::grid::{Grid, GridOptions, format_path};
+use crate::crossword::Crossword;
+use crate::wordle::Guess;
+use crate::letterbox::LetterBox;
+use crate::sequence::Gaps;
+use crate::filter::ResultFilter;
+use crate::template::{self, Quoting, RegexTemplate};
+use crate::error::SiftError;
+use crate::budget::Work;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// A single result of running a command: usually a dictionary word, with
+/// optional detail about how it was found.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SiftMatch<'a> {
+    pub word: Cow<'a, str>,
+    pub detail: Option<String>,
+}
+
+impl<'a> SiftMatch<'a> {
+    pub fn with_detail<W>(word: W, detail: String) -> SiftMatch<'a> where W: Into<Cow<'a, str>> {
+        SiftMatch { word: word.into(), detail: Some(detail) }
+    }
+}
+
+impl<'a> From<&'a str> for SiftMatch<'a> {
+    fn from(word: &'a str) -> SiftMatch<'a> {
+        SiftMatch { word: Cow::Borrowed(word), detail: None }
+    }
+}
+
+impl From<String> for SiftMatch<'_> {
+    fn from(word: String) -> Self {
+        SiftMatch { word: Cow::Owned(word), detail: None }
+    }
+}
+
+impl fmt::Display for SiftMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {}", self.word, detail),
+            None => write!(f, "{}", self.word),
+        }
+    }
+}
+
+/// How to combine the results of two commands, by word.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetOp {
+    Intersection,
+    Union,
+    Difference,
+}
+
+impl SetOp {
+    /// The operator for a command-line token: '&', '+' or '-'.
+    pub fn from_token(token: &str) -> Option<SetOp> {
+        match token {
+            "&" => Some(SetOp::Intersection),
+            "+" => Some(SetOp::Union),
+            "-" => Some(SetOp::Difference),
+            _ => None,
+        }
+    }
+
+    /// Combines two sets of results, keeping the first match for each word
+    /// and the order the left side gave them in.
+    pub fn apply<'a>(&self, left: Vec<SiftMatch<'a>>, right: Vec<SiftMatch<'a>>) -> Vec<SiftMatch<'a>> {
+        let right_words: HashSet<String> = right.iter().map(|m| m.word.to_string()).collect();
+        let mut seen = HashSet::new();
+        let mut results: Vec<SiftMatch> = left.into_iter()
+            .filter(|m| match self {
+                SetOp::Intersection => right_words.contains(m.word.as_ref()),
+                SetOp::Union => true,
+                SetOp::Difference => !right_words.contains(m.word.as_ref()),
+            })
+            .filter(|m| seen.insert(m.word.to_string()))
+            .collect();
+        if *self == SetOp::Union {
+            results.extend(right.into_iter().filter(|m| seen.insert(m.word.to_string())));
+        }
+        results
+    }
+}
+
+/// How to order a command's results.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    /// Alphabetically.
+    Alpha,
+    /// Fewest letters first, then alphabetically.
+    Length,
+    /// Most common in the word list first, then alphabetically.
+    Freq,
+    /// In whatever order the command produced them.
+    Unsorted,
+}
+
+fn letter_count(word: &str) -> usize {
+    word.chars().filter(|c| c.is_alphabetic()).count()
+}
+
+impl SortOrder {
+    pub fn from_name(name: &str) -> Option<SortOrder> {
+        match name {
+            "alpha" => Some(SortOrder::Alpha),
+            "length" => Some(SortOrder::Length),
+            "freq" => Some(SortOrder::Freq),
+            "none" => Some(SortOrder::Unsorted),
+            _ => None,
+        }
+    }
+
+    /// Sorts results in place. Ties are broken by word and then by detail, so
+    /// the order doesn't depend on how the command found them.
+    pub fn sort(&self, results: &mut [SiftMatch], sifter: &Sifter) {
+        let alpha = |a: &SiftMatch, b: &SiftMatch| (&a.word, &a.detail).cmp(&(&b.word, &b.detail));
+        match self {
+            SortOrder::Alpha => results.sort_by(alpha),
+            SortOrder::Length => results.sort_by(|a, b| letter_count(&a.word).cmp(&letter_count(&b.word)).then_with(|| alpha(a, b))),
+            SortOrder::Freq => {
+                results.sort_by(alpha);
+                results.sort_by_cached_key(|result| Reverse(sifter.frequency(&result.word)));
+            },
+            SortOrder::Unsorted => (),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub enum SiftCommand {
-    RegExp(Regex),
+    RegExp(RegexTemplate),
     Anagram(String),
     Bank(String),
     TransposeDelete(String, usize),
@@ -11,38 +146,328 @@ pub enum SiftCommand {
     Delete(String, usize),
     Add(String, usize),
     Change(String, usize),
+    Behead(String, usize),
+    Curtail(String, usize),
+    Gut(String, usize),
+    DeleteAlternate(String),
+    DeleteAt(String, Vec<usize>),
+    Prefix(String, usize),
+    Suffix(String, usize),
+    Infix(String, usize),
+    Extract(String, Selection, Transform),
+    Acrostic(String, bool, Option<String>),
+    Sum(RegexTemplate, ScoreScheme, RangeInclusive<u32>, bool),
+    Keypad(String),
+    Decode(Encoding, String, bool, usize),
+    Encode(Encoding, String),
+    Segment(String, usize, usize, bool),
+    WordGrid(Grid, GridOptions),
+    WordSearch(Grid, Vec<String>, bool),
+    Fill(Crossword, usize, bool),
+    Wordle(usize, Vec<Guess>, Option<usize>, bool),
+    SpellingBee(String, char, usize, bool),
+    LetterBoxed(LetterBox, usize, bool),
+    Subsequence(String, Gaps),
+    Supersequence(String, Gaps, bool),
+    Combine(SetOp, Box<SiftCommand>, Box<SiftCommand>),
+    Filter(Box<SiftCommand>, ResultFilter),
+    Sort(Box<SiftCommand>, SortOrder),
 }
 use SiftCommand::*;
 
+/// Lists the words each slot can take, so a unique fill gives one word per
+/// slot. With `show_fills`, prints each fill as a grid. A note follows when
+/// the search stopped at its limit, or found no fill and so lists whatever the
+/// crossings leave.
+fn crossword_fill<'a>(sifter: &'a Sifter, crossword: &Crossword, limit: usize, show_fills: bool,
+                      work: &Work) -> Vec<SiftMatch<'a>> {
+    let slots = crossword.slots();
+    let solution = sifter.fill_within(crossword, limit, work);
+    let mut results: Vec<SiftMatch> = if show_fills {
+        solution.fills.iter().map(|fill| SiftMatch::from(crossword.render(&slots, fill))).collect()
+    } else {
+        slots.iter().zip(&solution.candidates)
+            .map(|(slot, words)| SiftMatch::with_detail(slot.to_string(), words.join(" ")))
+            .collect()
+    };
+    let reason = if solution.limited {
+        format!("stopped after {} fills", limit)
+    } else if solution.fills.is_empty() && !show_fills {
+        "no complete fill".to_string()
+    } else {
+        return results;
+    };
+    let note = match show_fills {
+        true => reason,
+        false => format!("{}, so slots list every candidate the crossings leave", reason),
+    };
+    results.push(SiftMatch::with_detail("note", note));
+    results
+}
+
+/// Finds each word in the grid, followed by the letters left unused.
+fn word_search<'a>(grid: &Grid, words: &[String], leftover_only: bool) -> Vec<SiftMatch<'a>> {
+    let paths: Vec<_> = words.iter().map(|word| grid.find_word(word)).collect();
+    let leftover = grid.leftover(paths.iter().flatten());
+    if leftover_only {
+        return vec![SiftMatch::from(leftover)];
+    }
+    let mut results: Vec<SiftMatch> = words.iter().zip(paths)
+        .map(|(word, path)| {
+            let detail = path.map_or_else(|| "not found".to_string(), |path| format_path(&path));
+            SiftMatch::with_detail(word.clone(), detail)
+        })
+        .collect();
+    results.push(SiftMatch::with_detail("leftover", leftover));
+    results
+}
+
 impl SiftCommand {
-    pub fn run<'a>(&self, sifter: &'a Sifter) -> Vec<&'a str> {
+    pub fn run<'a>(&self, sifter: &'a Sifter) -> Vec<SiftMatch<'a>> {
+        self.run_within(sifter, &Work::unlimited())
+    }
+
+    /// Runs the command, stopping early with the results so far if it uses up
+    /// the work's budget. Results are sorted alphabetically unless the command
+    /// keeps its own order or is a `Sort` asking for another.
+    pub fn run_within<'a>(&self, sifter: &'a Sifter, work: &Work) -> Vec<SiftMatch<'a>> {
+        let mut results = self.run_unsorted(sifter, work);
+        if !matches!(self, Sort(_, _)) && !self.keeps_own_order() {
+            SortOrder::Alpha.sort(&mut results, sifter);
+        }
+        results
+    }
+
+    fn run_unsorted<'a>(&self, sifter: &'a Sifter, work: &Work) -> Vec<SiftMatch<'a>> {
+        let words = match self {
+            RegExp(pattern) => sifter.regex_within(pattern.regex(), work),
+            Anagram(letters) => sifter.anagrams(letters),
+            Bank(letters) => sifter.bank(letters),
+            TransposeDelete(letters, n) => sifter.transpose_delete_within(letters, *n, work),
+            TransposeAdd(letters, n) => sifter.transpose_add_within(letters, *n, work),
+            Delete(letters, n) => sifter.delete_within(letters, *n, work),
+            Add(letters, n) => sifter.add_within(letters, *n, work),
+            Change(letters, n) => sifter.change_within(letters, *n, work),
+            Behead(letters, n) => sifter.behead(letters, *n),
+            Curtail(letters, n) => sifter.curtail(letters, *n),
+            Gut(letters, n) => sifter.gut(letters, *n),
+            DeleteAlternate(letters) => sifter.delete_alternate(letters),
+            DeleteAt(letters, positions) => sifter.delete_at(letters, positions),
+            Prefix(letters, n) => sifter.prefix(letters, *n),
+            Suffix(letters, n) => sifter.suffix(letters, *n),
+            Infix(letters, n) => sifter.infix(letters, *n),
+            Extract(phrase, selection, transform) => sifter.extract(phrase, selection, *transform),
+            Acrostic(phrase, last, target) => {
+                // An unknown target is reported by `check`.
+                return sifter.acrostic_within(phrase, *last, target.as_deref(), work).unwrap_or_default().into_iter()
+                    .map(|(word, combo)| SiftMatch::with_detail(word, combo))
+                    .collect();
+            },
+            Sum(pattern, scheme, range, show_score) => {
+                return sifter.sum_within(pattern.regex(), scheme, range, work).into_iter()
+                    .map(|(word, score)| if *show_score {
+                        SiftMatch::with_detail(word, score.to_string())
+                    } else {
+                        SiftMatch::from(word)
+                    })
+                    .collect();
+            },
+            Keypad(input) if keypad::is_digits(input) => sifter.keypad(input),
+            Keypad(input) => {
+                return keypad::to_digits(input).into_iter()
+                    .map(SiftMatch::from)
+                    .collect();
+            },
+            Decode(encoding, message, true, max_words) => {
+                return sifter.decode_unspaced_within(*encoding, message, *max_words, work).into_iter()
+                    .map(SiftMatch::from)
+                    .collect();
+            },
+            Decode(encoding, message, false, _) => {
+                return encoding.decode(message).into_iter().map(SiftMatch::from).collect();
+            },
+            Encode(encoding, message) => {
+                return encoding.encode(message).into_iter().map(SiftMatch::from).collect();
+            },
+            Segment(letters, max_words, min_len, true) => {
+                return sifter.best_segment(letters, *max_words, *min_len).into_iter().map(SiftMatch::from).collect();
+            },
+            Segment(letters, max_words, min_len, false) => {
+                return sifter.segment_within(letters, *max_words, *min_len, work).into_iter().map(SiftMatch::from).collect();
+            },
+            WordGrid(grid, options) => {
+                return sifter.grid_within(grid, options, work).into_iter()
+                    .map(|(word, path)| SiftMatch::with_detail(word, format_path(&path)))
+                    .collect();
+            },
+            WordSearch(grid, words, leftover_only) => return word_search(grid, words, *leftover_only),
+            Fill(crossword, limit, show_fills) => return crossword_fill(sifter, crossword, *limit, *show_fills, work),
+            Wordle(length, guesses, None, _) => sifter.wordle(*length, guesses),
+            Wordle(length, guesses, Some(top), any_word) => {
+                return sifter.wordle_suggestions_within(*length, guesses, *any_word, work).into_iter()
+                    .take(*top)
+                    .map(|(word, bits)| SiftMatch::with_detail(word, format!("{:.2}", bits)))
+                    .collect();
+            },
+            SpellingBee(letters, center, min_len, pangrams_only) => {
+                return sifter.spelling_bee_within(letters, *center, *min_len, work).into_iter()
+                    .filter(|(_, pangram)| *pangram || !pangrams_only)
+                    .map(|(word, pangram)| if pangram {
+                        SiftMatch::with_detail(word, "pangram".to_string())
+                    } else {
+                        SiftMatch::from(word)
+                    })
+                    .collect();
+            },
+            LetterBoxed(letter_box, _, true) => sifter.letter_boxed_words(letter_box),
+            LetterBoxed(letter_box, max_words, false) => {
+                return sifter.letter_boxed(letter_box, *max_words).into_iter()
+                    .map(|chain| SiftMatch::from(chain.join(" ")))
+                    .collect();
+            },
+            Subsequence(letters, gaps) => sifter.subsequence(letters, gaps),
+            Supersequence(letters, gaps, kangaroo) => sifter.supersequence(letters, gaps, *kangaroo),
+            Combine(op, left, right) => {
+                let left = left.run_within(sifter, work);
+                // What's taken away isn't capped, or capping it would let
+                // through words it should have removed.
+                let right = match op {
+                    SetOp::Difference => work.uncapped(|work| right.run_within(sifter, work)),
+                    _ => right.run_within(sifter, work),
+                };
+                return op.apply(left, right);
+            },
+            Filter(command, filter) => {
+                let mut results = command.run_within(sifter, work);
+                results.retain(|result| filter.keeps(&result.word, sifter));
+                return results;
+            },
+            Sort(command, order) => {
+                let mut results = command.run_within(sifter, work);
+                order.sort(&mut results, sifter);
+                return results;
+            },
+        };
+        words.into_iter().map(SiftMatch::from).collect()
+    }
+
+    /// Checks what can only be checked against the dictionary, like an
+    /// acrostic's target, before the command runs.
+    pub fn check(&self, sifter: &Sifter) -> Result<(), SiftError> {
         match self {
-            RegExp(regex) => sifter.regex(&regex),
-            Anagram(letters) => sifter.anagrams(&letters),
-            Bank(letters) => sifter.bank(&letters),
-            TransposeDelete(letters, n) => sifter.transpose_delete(&letters, *n),
-            TransposeAdd(letters, n) => sifter.transpose_add(&letters, *n),
-            Delete(letters, n) => sifter.delete(&letters, *n),
-            Add(letters, n) => sifter.add(&letters, *n),
-            Change(letters, n) => sifter.change(&letters, *n),
+            Acrostic(_, _, Some(target)) if !sifter.has_tag(&target.to_ascii_lowercase(), "*") => Err(SiftError::UnknownWord),
+            Combine(_, left, right) => left.check(sifter).and_then(|_| right.check(sifter)),
+            Filter(command, _) | Sort(command, _) => command.check(sifter),
+            _ => Ok(()),
         }
     }
 
-    pub fn substitute(&self, word: &str) -> SiftCommand {
+    /// Whether this command should be run once over all piped inputs,
+    /// rather than once per input.
+    pub fn takes_all_inputs(&self) -> bool {
         match self {
-            RegExp(regex) => {
-                let new_regex = Regex::new(&regex.as_str().replace("%", word)).unwrap();
-                RegExp(new_regex)
+            Acrostic(phrase, _, _) => phrase.is_empty(),
+            WordSearch(_, words, _) => words.is_empty(),
+            Combine(_, left, right) => left.takes_all_inputs() || right.takes_all_inputs(),
+            Filter(command, _) => command.takes_all_inputs(),
+            Sort(command, _) => command.takes_all_inputs(),
+            _ => false,
+        }
+    }
+
+    /// Whether the command gives its results in a meaningful order, such as
+    /// best first, which sorting by default would lose.
+    pub fn keeps_own_order(&self) -> bool {
+        match self {
+            Keypad(input) => !keypad::is_digits(input),
+            Decode(_, _, unspaced, _) => !unspaced,
+            Encode(_, _) | WordSearch(_, _, _) | Fill(_, _, _) => true,
+            Segment(_, _, _, best) => *best,
+            Wordle(_, _, top, _) => top.is_some(),
+            LetterBoxed(_, _, words_only) => !words_only,
+            Combine(_, left, _) => left.keeps_own_order(),
+            Filter(command, _) | Sort(command, _) => command.keeps_own_order(),
+            _ => false,
+        }
+    }
+
+    /// Builds a command from all piped inputs at once. For an acrostic, each
+    /// input line gives the candidates for one position; for a word search,
+    /// each line gives a word to find.
+    pub fn gather(&self, inputs: &[&str]) -> SiftCommand {
+        match self {
+            Acrostic(_, last, target) => {
+                let positions: Vec<String> = inputs.iter()
+                    .filter_map(|line| line.rsplit("=>").next())
+                    .map(|candidates| phrase_words(candidates).join("/"))
+                    .filter(|candidates| !candidates.is_empty())
+                    .collect();
+                Acrostic(positions.join(" "), *last, target.clone())
+            },
+            WordSearch(grid, _, leftover_only) => {
+                let words = inputs.iter()
+                    .filter_map(|line| line.rsplit("=>").next())
+                    .map(|word| phrase_words(word).concat())
+                    .filter(|word| !word.is_empty())
+                    .collect();
+                WordSearch(grid.clone(), words, *leftover_only)
             },
-            Anagram(letters) => Anagram(letters.replace("%", word)),
-            Bank(letters) => Bank(letters.replace("%", word)),
-            TransposeDelete(letters, n) => TransposeDelete(letters.replace("%", word), *n),
-            TransposeAdd(letters, n) => TransposeAdd(letters.replace("%", word), *n),
-            Delete(letters, n) => Delete(letters.replace("%", word), *n),
-            Add(letters, n) => Add(letters.replace("%", word), *n),
-            Change(letters, n) => Change(letters.replace("%", word), *n),
+            Combine(op, left, right) => Combine(*op, Box::new(left.gather(inputs)), Box::new(right.gather(inputs))),
+            Filter(command, filter) => Filter(Box::new(command.gather(inputs)), filter.clone()),
+            Sort(command, order) => Sort(Box::new(command.gather(inputs)), *order),
+            _ => self.clone(),
         }
     }
+
+    /// Fills in the placeholders in the command's arguments from the words
+    /// each stage of a pipeline produced, first to last. `quoting` decides
+    /// whether words filled into a regular expression are escaped.
+    pub fn substitute(&self, stages: &[&str], quoting: Quoting) -> Result<SiftCommand, SiftError> {
+        let sub = |text: &str| template::substitute(text, stages);
+        Ok(match self {
+            RegExp(pattern) => RegExp(pattern.substitute(stages, quoting)?),
+            Anagram(letters) => Anagram(sub(letters)?),
+            Bank(letters) => Bank(sub(letters)?),
+            TransposeDelete(letters, n) => TransposeDelete(sub(letters)?, *n),
+            TransposeAdd(letters, n) => TransposeAdd(sub(letters)?, *n),
+            Delete(letters, n) => Delete(sub(letters)?, *n),
+            Add(letters, n) => Add(sub(letters)?, *n),
+            Change(letters, n) => Change(sub(letters)?, *n),
+            Behead(letters, n) => Behead(sub(letters)?, *n),
+            Curtail(letters, n) => Curtail(sub(letters)?, *n),
+            Gut(letters, n) => Gut(sub(letters)?, *n),
+            DeleteAlternate(letters) => DeleteAlternate(sub(letters)?),
+            DeleteAt(letters, positions) => DeleteAt(sub(letters)?, positions.clone()),
+            Prefix(letters, n) => Prefix(sub(letters)?, *n),
+            Suffix(letters, n) => Suffix(sub(letters)?, *n),
+            Infix(letters, n) => Infix(sub(letters)?, *n),
+            Extract(phrase, selection, transform) => Extract(sub(phrase)?, selection.clone(), *transform),
+            Acrostic(phrase, last, target) => Acrostic(sub(phrase)?, *last, target.clone()),
+            Sum(pattern, scheme, range, show_score) => {
+                Sum(pattern.substitute(stages, quoting)?, scheme.clone(), range.clone(), *show_score)
+            },
+            Keypad(input) => Keypad(sub(input)?),
+            Decode(encoding, message, unspaced, max_words) => Decode(*encoding, sub(message)?, *unspaced, *max_words),
+            Encode(encoding, message) => Encode(*encoding, sub(message)?),
+            Segment(letters, max_words, min_len, best) => Segment(sub(letters)?, *max_words, *min_len, *best),
+            WordGrid(grid, options) => WordGrid(grid.clone(), *options),
+            WordSearch(grid, words, leftover_only) => {
+                let words = words.iter().map(|w| sub(w)).collect::<Result<Vec<String>, SiftError>>()?;
+                WordSearch(grid.clone(), words, *leftover_only)
+            },
+            Fill(crossword, limit, show_fills) => Fill(crossword.clone(), *limit, *show_fills),
+            Wordle(length, guesses, top, any_word) => Wordle(*length, guesses.clone(), *top, *any_word),
+            SpellingBee(letters, center, min_len, pangrams_only) => {
+                SpellingBee(sub(letters)?, *center, *min_len, *pangrams_only)
+            },
+            LetterBoxed(letter_box, max_words, words_only) => LetterBoxed(letter_box.clone(), *max_words, *words_only),
+            Subsequence(letters, gaps) => Subsequence(sub(letters)?, *gaps),
+            Supersequence(letters, gaps, kangaroo) => Supersequence(sub(letters)?, *gaps, *kangaroo),
+            Combine(op, left, right) => Combine(*op, Box::new(left.substitute(stages, quoting)?), Box::new(right.substitute(stages, quoting)?)),
+            Filter(command, filter) => Filter(Box::new(command.substitute(stages, quoting)?), filter.clone()),
+            Sort(command, order) => Sort(Box::new(command.substitute(stages, quoting)?), *order),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -51,25 +476,124 @@ mod tests {
 
     #[test]
     fn test_substitute() {
-        let cmd = SiftCommand::RegExp(Regex::new("..%..%").unwrap());
-        if let SiftCommand::RegExp(r) = cmd.substitute("foobar") {
+        let cmd = SiftCommand::RegExp(RegexTemplate::new("..%..%").unwrap());
+        if let SiftCommand::RegExp(r) = cmd.substitute(&["foobar"], Quoting::Literal).unwrap() {
             assert_eq!(r.as_str(), "..foobar..foobar");
         } else {
             panic!("got wrong variant back from substitute");
         }
 
         let cmd = SiftCommand::Anagram("blah%blah%blah".to_string());
-        if let SiftCommand::Anagram(r) = cmd.substitute("x") {
+        if let SiftCommand::Anagram(r) = cmd.substitute(&["x"], Quoting::Literal).unwrap() {
             assert_eq!(r, "blahxblahxblah");
         } else {
             panic!("got wrong variant back from substitute");
         }
 
         let cmd = SiftCommand::TransposeDelete("blah%blah%blah".to_string(), 5);
-        if let SiftCommand::TransposeDelete(r, 5) = cmd.substitute("x") {
+        if let SiftCommand::TransposeDelete(r, 5) = cmd.substitute(&["x"], Quoting::Literal).unwrap() {
             assert_eq!(r, "blahxblahxblah");
         } else {
             panic!("got wrong variant back from substitute");
         }
+
+        let cmd = SiftCommand::Bank("%{1}%{1:}".to_string());
+        if let SiftCommand::Bank(r) = cmd.substitute(&["h", "oses"], Quoting::Literal).unwrap() {
+            assert_eq!(r, "hses");
+        } else {
+            panic!("got wrong variant back from substitute");
+        }
+        assert!(matches!(cmd.substitute(&[], Quoting::Literal), Err(SiftError::MissingStage)));
+        let cmd = SiftCommand::RegExp(RegexTemplate::new("%.").unwrap());
+        assert!(matches!(cmd.substitute(&["("], Quoting::Raw), Err(SiftError::InvalidRegExp)));
+        if let SiftCommand::RegExp(r) = cmd.substitute(&["("], Quoting::Literal).unwrap() {
+            assert_eq!(r.as_str(), r"\(.");
+        } else {
+            panic!("got wrong variant back from substitute");
+        }
+        let cmd = SiftCommand::Sum(RegexTemplate::new("%.").unwrap(), ScoreScheme::A1Z26, 1..=100, false);
+        assert!(matches!(cmd.substitute(&["("], Quoting::Raw), Err(SiftError::InvalidRegExp)));
+        if let SiftCommand::Sum(r, _, _, false) = cmd.substitute(&["("], Quoting::Literal).unwrap() {
+            assert_eq!(r.as_str(), r"\(.");
+        } else {
+            panic!("got wrong variant back from substitute");
+        }
+    }
+
+    #[test]
+    fn test_gather() {
+        let cmd = SiftCommand::Acrostic(String::new(), false, None);
+        assert!(cmd.takes_all_inputs());
+        if let SiftCommand::Acrostic(phrase, false, None) = cmd.gather(&["hat sun", "x => ox, owl", ""]) {
+            assert_eq!(phrase, "hat/sun ox/owl");
+        } else {
+            panic!("got wrong variant back from gather");
+        }
+        assert!(!SiftCommand::Acrostic("hat".to_string(), false, None).takes_all_inputs());
+    }
+
+    #[test]
+    fn test_word_search() {
+        let grid = Grid::parse("cat xox god").unwrap();
+        let cmd = SiftCommand::WordSearch(grid, vec![], false);
+        assert!(cmd.takes_all_inputs());
+        let cmd = cmd.gather(&["cat", "x => dog", "emu"]);
+        let results: Vec<String> = word_search_results(&cmd);
+        assert_eq!(results, vec![
+            "cat: (1,1) (1,2) (1,3)",
+            "dog: (3,3) (3,2) (3,1)",
+            "emu: not found",
+            "leftover: xox",
+        ]);
+    }
+
+    fn word_search_results(cmd: &SiftCommand) -> Vec<String> {
+        match cmd {
+            SiftCommand::WordSearch(grid, words, leftover_only) => word_search(grid, words, *leftover_only)
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            _ => panic!("expected a word search"),
+        }
+    }
+
+    #[test]
+    fn test_sort_order() {
+        let sifter = Sifter::new_from_words("hose 5\nshoe 9\nhoses 9\n".as_bytes());
+        let sorted = |order: SortOrder| {
+            let mut results = vec![SiftMatch::from("shoe"), SiftMatch::from("hose shoe"), SiftMatch::from("hoses"), SiftMatch::from("hose")];
+            order.sort(&mut results, &sifter);
+            results.iter().map(|result| result.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(sorted(SortOrder::Alpha), vec!["hose", "hose shoe", "hoses", "shoe"]);
+        assert_eq!(sorted(SortOrder::Length), vec!["hose", "shoe", "hoses", "hose shoe"]);
+        assert_eq!(sorted(SortOrder::Freq), vec!["hoses", "shoe", "hose", "hose shoe"]);
+        assert_eq!(sorted(SortOrder::Unsorted), vec!["shoe", "hose shoe", "hoses", "hose"]);
+        assert_eq!(SortOrder::from_name("none"), Some(SortOrder::Unsorted));
+
+        let words = |command: SiftCommand| -> Vec<String> {
+            command.run(&sifter).iter().map(|result| result.to_string()).collect()
+        };
+        assert_eq!(words(Bank("hose".to_string())), vec!["hose", "hoses", "shoe"]);
+        let command = Sort(Box::new(Bank("hose".to_string())), SortOrder::Freq);
+        assert_eq!(words(command), vec!["hoses", "shoe", "hose"]);
+    }
+
+    #[test]
+    fn test_set_ops() {
+        let left = || vec![SiftMatch::from("hose"), SiftMatch::with_detail("rose", "1".to_string()), SiftMatch::from("hose")];
+        let right = || vec![SiftMatch::with_detail("rose", "2".to_string()), SiftMatch::from("shoe")];
+        let words = |results: Vec<SiftMatch>| -> Vec<String> { results.iter().map(|m| m.to_string()).collect() };
+        assert_eq!(words(SetOp::Intersection.apply(left(), right())), vec!["rose: 1"]);
+        assert_eq!(words(SetOp::Union.apply(left(), right())), vec!["hose", "rose: 1", "shoe"]);
+        assert_eq!(words(SetOp::Difference.apply(left(), right())), vec!["hose"]);
+        assert_eq!(SetOp::from_token("&"), Some(SetOp::Intersection));
+        assert_eq!(SetOp::from_token("x"), None);
+    }
+
+    #[test]
+    fn test_match_display() {
+        assert_eq!(SiftMatch::from("hose").to_string(), "hose");
+        assert_eq!(SiftMatch::with_detail("hose", "hungry owls".to_string()).to_string(), "hose: hungry owls");
     }
 }
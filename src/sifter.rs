@@ -1,14 +1,28 @@
 use crate::dictionary::{Dictionary, sort_letters};
-use crate::argparse::SiftError;
+use crate::error::SiftError;
+use crate::extract::{extract, Selection, Transform};
+use crate::score::ScoreScheme;
+use crate::keypad;
+use crate::encoding::Encoding;
+use crate::trie::TrieNode;
+use crate::grid::{Grid, GridOptions};
+use crate::crossword::{self, Crossword, Solution};
+use crate::wordle::{self, Guess};
+use crate::letterbox::{self, LetterBox};
+use crate::sequence::{self, Gaps};
+use crate::parallel;
+use crate::budget::Work;
+use std::ops::RangeInclusive;
 use std::path::Path;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::prelude::*;
 use regex::Regex;
 use itertools::Itertools;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 use std::fs::File;
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Sifter {
     dict: Dictionary,
 }
@@ -27,10 +41,15 @@ fn open<P>(path: P) -> Result<File, SiftError> where P: AsRef<Path> {
     })
 }
 
-fn all_added_wildcards(letters: &str, n: usize) -> Vec<String> {
+/// Each way of adding n wildcards among the letters, until the work runs
+/// out.
+fn all_added_wildcards(letters: &str, n: usize, work: &Work) -> Vec<String> {
     let mut words = Vec::new();
     let orig_chars: Vec<char> = letters.chars().collect();
     for combo in (0..letters.len() + n).combinations(letters.len()) {
+        if !work.allows_pattern(words.len()) {
+            break;
+        }
         let mut new_word = vec!['.'; letters.len() + n];
         for (letters_i, &new_word_i) in combo.iter().enumerate() {
             new_word[new_word_i] = orig_chars[letters_i];
@@ -40,9 +59,12 @@ fn all_added_wildcards(letters: &str, n: usize) -> Vec<String> {
     words
 }
 
-fn all_deletes(letters: &str, n: usize) -> Vec<String> {
+fn all_deletes(letters: &str, n: usize, work: &Work) -> Vec<String> {
     let mut words = Vec::new();
     for combo in (0..letters.len()).combinations(n) {
+        if !work.allows_pattern(words.len()) {
+            break;
+        }
         let new_word: String = letters.chars().enumerate().filter_map(|(i, c)| {
             if combo.contains(&i) {
                 None
@@ -55,9 +77,12 @@ fn all_deletes(letters: &str, n: usize) -> Vec<String> {
     words
 }
 
-fn all_replaced_wildcards(letters: &str, n: usize) -> Vec<String> {
+fn all_replaced_wildcards(letters: &str, n: usize, work: &Work) -> Vec<String> {
     let mut words = Vec::new();
     for combo in (0..letters.len()).combinations(n) {
+        if !work.allows_pattern(words.len()) {
+            break;
+        }
         let new_word: String = letters.chars().enumerate().map(|(i, c)| {
             if combo.contains(&i) {
                 '.'
@@ -70,6 +95,161 @@ fn all_replaced_wildcards(letters: &str, n: usize) -> Vec<String> {
     words
 }
 
+fn all_gutted(letters: &str, n: usize) -> Vec<String> {
+    let chars: Vec<char> = letters.chars().collect();
+    if n == 0 || n + 2 > chars.len() {
+        return vec![letters.to_string()];
+    }
+    (1..chars.len() - n).map(|start| {
+        chars[..start].iter().chain(&chars[start + n..]).collect()
+    }).collect()
+}
+
+fn all_infixed_wildcards(letters: &str, n: usize) -> Vec<String> {
+    let chars: Vec<char> = letters.chars().collect();
+    if n == 0 || chars.len() < 2 {
+        return vec![letters.to_string()];
+    }
+    let wildcards = vec!['.'; n];
+    (1..chars.len()).map(|start| {
+        chars[..start].iter().chain(&wildcards).chain(&chars[start..]).collect()
+    }).collect()
+}
+
+/// Follows the trie through the letters whose codes spell the message from
+/// pos, adding an edge to where each word's codes end.
+fn walk_codes(dict: &Dictionary, node: &TrieNode, codes: &[(char, String)], message: &str, pos: usize,
+              edges: &mut Vec<(usize, usize)>) {
+    if let Some(&idx) = node.words().iter().max_by_key(|&&idx| dict.frequency(idx)) {
+        edges.push((pos, idx));
+    }
+    for (letter, child) in node.children() {
+        if let Some((_, code)) = codes.iter().find(|(code_letter, _)| *code_letter == letter) {
+            if message[pos..].starts_with(code.as_str()) {
+                walk_codes(dict, child, codes, message, pos + code.len(), edges);
+            }
+        }
+    }
+}
+
+/// Adds each segmentation from pos onwards to the results, returning false
+/// once the work runs out.
+fn segment_walk(edges: &[Vec<(usize, usize)>], min_words: &[usize], pos: usize, words_left: usize,
+                path: &mut Vec<usize>, results: &mut Vec<Vec<usize>>, work: &Work) -> bool {
+    if !work.has_time() {
+        return false;
+    }
+    if pos == edges.len() {
+        if !work.allows_result(results.len()) {
+            return false;
+        }
+        results.push(path.clone());
+        return true;
+    }
+    for &(end, idx) in &edges[pos] {
+        if min_words[end] < words_left {
+            path.push(idx);
+            let more = segment_walk(edges, min_words, end, words_left - 1, path, results, work);
+            path.pop();
+            if !more {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Adds each way of picking one candidate per position to the combos,
+/// returning false once the work runs out. `found` counts earlier results.
+fn pick_each<'a>(positions: &[Vec<&'a str>], picked: &mut Vec<&'a str>, found: usize,
+                 combos: &mut Vec<String>, work: &Work) -> bool {
+    if !work.has_time() {
+        return false;
+    }
+    match positions.split_first() {
+        None => {
+            if !work.allows_result(found + combos.len()) {
+                return false;
+            }
+            combos.push(picked.join(" "));
+            true
+        }
+        Some((candidates, rest)) => {
+            for &candidate in candidates {
+                picked.push(candidate);
+                let more = pick_each(rest, picked, found, combos, work);
+                picked.pop();
+                if !more {
+                    return false;
+                }
+            }
+            true
+        }
+    }
+}
+
+fn walk_sides(node: &TrieNode, letter_box: &LetterBox, last_side: Option<usize>, depth: usize, results: &mut Vec<usize>) {
+    if depth >= 3 {
+        results.extend(node.words());
+    }
+    for (letter, child) in node.children() {
+        if let Some(side) = letter_box.side(letter) {
+            if last_side != Some(side) {
+                walk_sides(child, letter_box, Some(side), depth + 1, results);
+            }
+        }
+    }
+}
+
+struct GridSearch<'a, 'g> {
+    dict: &'a Dictionary,
+    grid: &'g Grid,
+    options: &'g GridOptions,
+    work: &'g Work,
+    path: Vec<(usize, usize)>,
+    found: HashMap<&'a str, Vec<(usize, usize)>>,
+    capped: bool,
+}
+
+impl<'a> GridSearch<'a, '_> {
+    /// Follows the trie from the given node through the grid, continuing in
+    /// the given direction only, or in every direction if there isn't one.
+    fn walk(&mut self, node: &TrieNode, pos: (usize, usize), direction: Option<(isize, isize)>) {
+        if self.capped || !self.work.has_time() {
+            return;
+        }
+        let node = match node.child(self.grid.get(pos)) {
+            Some(child) => child,
+            None => return,
+        };
+        self.path.push(pos);
+        if self.path.len() >= self.options.min_len {
+            for &idx in node.words() {
+                let word = self.dict.word(idx);
+                if !self.found.contains_key(word) {
+                    if !self.work.allows_result(self.found.len()) {
+                        self.capped = true;
+                        break;
+                    }
+                    self.found.insert(word, self.path.clone());
+                }
+            }
+        }
+        let directions = match direction {
+            Some(direction) => vec![direction],
+            None => self.options.directions().to_vec(),
+        };
+        for step in directions {
+            if let Some(next) = self.grid.step(pos, step, self.options.wrap) {
+                if self.options.reuse || !self.path.contains(&next) {
+                    self.walk(node, next, direction);
+                }
+            }
+        }
+        self.path.pop();
+    }
+}
+
 impl Sifter {
     pub fn new_from_cache<R>(data: R) -> Result<Sifter, SiftError> where R: Read {
         Ok(Sifter { dict: Dictionary::new_from_cache(data)? })
@@ -99,55 +279,596 @@ impl Sifter {
     }
 
     pub fn regex(&self, pattern: &Regex) -> Vec<&str> {
+        self.regex_within(pattern, &Work::unlimited())
+    }
+
+    /// Like `regex`, stopping once the work runs out.
+    pub fn regex_within(&self, pattern: &Regex, work: &Work) -> Vec<&str> {
         let whole_word_pattern = format!("^{}$", pattern.as_str());
         let regex = Regex::new(&whole_word_pattern).unwrap();
-        self.dict.words()
-            .into_iter()
-            .filter(|word| regex.is_match(word))
-            .collect()
+        work.collect_matching(self.dict.words(), |word| regex.is_match(word))
+    }
+
+    /// Words matching the pattern whose letter sum falls within the range,
+    /// along with their sums.
+    pub fn sum(&self, pattern: &Regex, scheme: &ScoreScheme, range: &RangeInclusive<u32>) -> Vec<(&str, u32)> {
+        self.sum_within(pattern, scheme, range, &Work::unlimited())
+    }
+
+    /// Like `sum`, stopping once the work runs out.
+    pub fn sum_within(&self, pattern: &Regex, scheme: &ScoreScheme, range: &RangeInclusive<u32>,
+                      work: &Work) -> Vec<(&str, u32)> {
+        let whole_word_pattern = format!("^{}$", pattern.as_str());
+        let regex = Regex::new(&whole_word_pattern).unwrap();
+        work.collect_matching(self.dict.scored_words(scheme),
+                              |(word, score)| range.contains(score) && regex.is_match(word))
+    }
+
+    /// Looks up each pattern, skipping the rest once the work runs out of
+    /// time.
+    fn lookup_each<'a, F>(&'a self, patterns: &[String], work: &Work, lookup: F) -> Vec<&'a str>
+        where F: Fn(&'a Dictionary, &str) -> HashSet<&'a str> + Sync + Send {
+        parallel::flat_map(patterns, |pattern| match work.has_time() {
+            true => lookup(&self.dict, pattern),
+            false => HashSet::new(),
+        })
     }
 
     pub fn transpose_delete(&self, letters: &str, n: usize) -> Vec<&str> {
+        self.transpose_delete_within(letters, n, &Work::unlimited())
+    }
+
+    /// Like `transpose_delete`, looking up patterns only until the work runs out.
+    pub fn transpose_delete_within(&self, letters: &str, n: usize, work: &Work) -> Vec<&str> {
         if n > letters.len() {
             return vec![];
         }
         let mut results = HashSet::new();
-        for new_word in all_deletes(letters, n) {
-            results.extend(self.dict.lookup_anagram(&new_word, true));
-        }
+        let new_words = all_deletes(letters, n, work);
+        results.extend(self.lookup_each(&new_words, work, |dict, new_word| dict.lookup_anagram(new_word, true)));
         results.remove(letters);
         results.into_iter().collect()
     }
 
     pub fn delete(&self, letters: &str, n: usize) -> Vec<&str> {
+        self.delete_within(letters, n, &Work::unlimited())
+    }
+
+    /// Like `delete`, looking up patterns only until the work runs out.
+    pub fn delete_within(&self, letters: &str, n: usize, work: &Work) -> Vec<&str> {
         if n > letters.len() {
             return vec![];
         }
         let mut results = HashSet::new();
-        for new_word in all_deletes(letters, n) {
+        let new_words = all_deletes(letters, n, work);
+        results.extend(self.lookup_each(&new_words, work, |dict, new_word| dict.lookup(new_word)));
+        results.into_iter().collect()
+    }
+
+
+    pub fn behead(&self, letters: &str, n: usize) -> Vec<&str> {
+        if n > letters.chars().count() {
+            return vec![];
+        }
+        let beheaded: String = letters.chars().skip(n).collect();
+        self.dict.lookup(&beheaded).into_iter().collect()
+    }
+
+    pub fn curtail(&self, letters: &str, n: usize) -> Vec<&str> {
+        let len = letters.chars().count();
+        if n > len {
+            return vec![];
+        }
+        let curtailed: String = letters.chars().take(len - n).collect();
+        self.dict.lookup(&curtailed).into_iter().collect()
+    }
+
+    pub fn gut(&self, letters: &str, n: usize) -> Vec<&str> {
+        if n + 2 > letters.chars().count() {
+            return vec![];
+        }
+        let mut results = HashSet::new();
+        let new_words = all_gutted(letters, n);
+        results.extend(parallel::flat_map(&new_words, |new_word| self.dict.lookup(new_word)));
+        results.into_iter().collect()
+    }
+
+    pub fn delete_alternate(&self, letters: &str) -> Vec<&str> {
+        let mut results = HashSet::new();
+        for parity in 0..2 {
+            let new_word: String = letters.chars().skip(parity).step_by(2).collect();
             results.extend(self.dict.lookup(&new_word));
         }
         results.into_iter().collect()
     }
 
+    /// Deletes the letters at the given 1-based positions.
+    pub fn delete_at(&self, letters: &str, positions: &[usize]) -> Vec<&str> {
+        let len = letters.chars().count();
+        if positions.iter().any(|&pos| pos == 0 || pos > len) {
+            return vec![];
+        }
+        let new_word: String = letters.chars().enumerate()
+            .filter(|(i, _)| !positions.contains(&(i + 1)))
+            .map(|(_, c)| c)
+            .collect();
+        self.dict.lookup(&new_word).into_iter().collect()
+    }
 
     pub fn transpose_add(&self, letters: &str, n: usize) -> Vec<&str> {
+        self.transpose_add_within(letters, n, &Work::unlimited())
+    }
+
+    /// Like `transpose_add`, looking up patterns only until the work runs out.
+    pub fn transpose_add_within(&self, letters: &str, n: usize, work: &Work) -> Vec<&str> {
         let mut results = HashSet::new();
-        for wildcard_string in all_added_wildcards(&sort_letters(letters), n) {
-            results.extend(self.dict.lookup_anagram(&wildcard_string, false));
-        }
+        let wildcard_strings = all_added_wildcards(&sort_letters(letters), n, work);
+        results.extend(self.lookup_each(&wildcard_strings, work, |dict, wildcard_string| dict.lookup_anagram(wildcard_string, false)));
         results.remove(letters);
         results.into_iter().collect()
     }
 
     pub fn add(&self, letters: &str, n: usize) -> Vec<&str> {
+        self.add_within(letters, n, &Work::unlimited())
+    }
+
+    /// Like `add`, looking up patterns only until the work runs out.
+    pub fn add_within(&self, letters: &str, n: usize, work: &Work) -> Vec<&str> {
         let mut results = HashSet::new();
-        for wildcard_string in all_added_wildcards(letters, n) {
-            results.extend(self.dict.lookup(&wildcard_string));
+        let wildcard_strings = all_added_wildcards(letters, n, work);
+        results.extend(self.lookup_each(&wildcard_strings, work, |dict, wildcard_string| dict.lookup(wildcard_string)));
+        results.into_iter().collect()
+    }
+
+    pub fn prefix(&self, letters: &str, n: usize) -> Vec<&str> {
+        let wildcard_string = format!("{}{}", ".".repeat(n), letters);
+        self.dict.lookup(&wildcard_string).into_iter().collect()
+    }
+
+    pub fn suffix(&self, letters: &str, n: usize) -> Vec<&str> {
+        let wildcard_string = format!("{}{}", letters, ".".repeat(n));
+        self.dict.lookup(&wildcard_string).into_iter().collect()
+    }
+
+    pub fn infix(&self, letters: &str, n: usize) -> Vec<&str> {
+        if letters.chars().count() < 2 {
+            return vec![];
         }
+        let mut results = HashSet::new();
+        let wildcard_strings = all_infixed_wildcards(letters, n);
+        results.extend(parallel::flat_map(&wildcard_strings, |wildcard_string| self.dict.lookup(wildcard_string)));
+        results.into_iter().collect()
+    }
+
+    pub fn extract(&self, phrase: &str, selection: &Selection, transform: Transform) -> Vec<&str> {
+        let letters = match extract(phrase, selection) {
+            Some(letters) if !letters.is_empty() => letters,
+            _ => return vec![],
+        };
+        let results = match transform {
+            Transform::Plain => self.dict.lookup(&letters),
+            Transform::Anagram => self.dict.lookup_anagram(&letters, true),
+            Transform::Reverse => self.dict.lookup(&letters.chars().rev().collect::<String>()),
+        };
         results.into_iter().collect()
     }
 
+    /// Finds words spelled by the first (or last) letters of each position in
+    /// the phrase, along with each combination of words that spells them.
+    /// Positions are separated by whitespace, and a position may list several
+    /// candidate words separated by '/' or ','. A target must be in the
+    /// dictionary.
+    pub fn acrostic(&self, phrase: &str, last: bool, target: Option<&str>) -> Result<Vec<(String, String)>, SiftError> {
+        self.acrostic_within(phrase, last, target, &Work::unlimited())
+    }
+
+    /// Like `acrostic`, stopping once the work runs out.
+    pub fn acrostic_within(&self, phrase: &str, last: bool, target: Option<&str>,
+                           work: &Work) -> Result<Vec<(String, String)>, SiftError> {
+        if let Some(target) = target {
+            if !self.has_tag(&target.to_ascii_lowercase(), "*") {
+                return Err(SiftError::UnknownWord);
+            }
+        }
+        let positions: Vec<Vec<&str>> = phrase.split_whitespace()
+            .map(|position| position.split(['/', ','])
+                .filter(|candidate| !candidate.is_empty())
+                .collect())
+            .collect();
+        if positions.is_empty() {
+            return Ok(vec![]);
+        }
+        let end_letter = |candidate: &str| {
+            let letter = if last { candidate.chars().last() } else { candidate.chars().next() };
+            letter.map(|c| c.to_ascii_lowercase())
+        };
+
+        let mut pattern = String::new();
+        for candidates in &positions {
+            let letters: String = candidates.iter()
+                .filter_map(|&candidate| end_letter(candidate))
+                .sorted()
+                .dedup()
+                .collect();
+            pattern.push('[');
+            pattern.push_str(&letters);
+            pattern.push(']');
+        }
+        let words: Vec<String> = match target {
+            Some(target) => vec![target.to_ascii_lowercase()],
+            None => self.dict.lookup(&pattern).into_iter().map(String::from).collect(),
+        };
+
+        // A word is dropped at the first position with no candidate for its
+        // letter, and its combinations are picked one at a time rather than
+        // all built up front.
+        let mut results = Vec::new();
+        for word in words {
+            if !work.has_time() {
+                break;
+            }
+            if word.chars().count() != positions.len() {
+                continue;
+            }
+            let matching: Vec<Vec<&str>> = positions.iter().zip(word.chars())
+                .map(|(candidates, letter)| candidates.iter()
+                    .filter(|&&candidate| end_letter(candidate) == Some(letter))
+                    .cloned()
+                    .collect())
+                .collect();
+            if matching.iter().any(|candidates| candidates.is_empty()) {
+                continue;
+            }
+            let mut combos = Vec::new();
+            let more = pick_each(&matching, &mut Vec::new(), results.len(), &mut combos, work);
+            results.extend(combos.into_iter().map(|combo| (word.clone(), combo)));
+            if !more {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Words spelled by the given telephone keypad digits.
+    pub fn keypad(&self, digits: &str) -> Vec<&str> {
+        match keypad::to_pattern(digits) {
+            Some(pattern) if !digits.is_empty() => self.dict.lookup(&pattern).into_iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Every way of splitting a message, whose letter codes are run together
+    /// without separators, into at most max_words dictionary words, most
+    /// probable first. A max_words of 0 means no limit.
+    pub fn decode_unspaced(&self, encoding: Encoding, message: &str, max_words: usize) -> Vec<String> {
+        self.decode_unspaced_within(encoding, message, max_words, &Work::unlimited())
+    }
+
+    /// Like `decode_unspaced`, ranking only the splits found before the work
+    /// runs out.
+    pub fn decode_unspaced_within(&self, encoding: Encoding, message: &str, max_words: usize,
+                                  work: &Work) -> Vec<String> {
+        let message: String = message.chars()
+            .filter(|&c| !c.is_whitespace() && c != '/')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let codes: Vec<(char, String)> = ('a'..='z')
+            .filter_map(|letter| encoding.encode_letter(letter).map(|code| (letter, code)))
+            .collect();
+        let mut edges = vec![Vec::new(); message.len()];
+        for (start, starting_edges) in edges.iter_mut().enumerate() {
+            if message.is_char_boundary(start) {
+                walk_codes(&self.dict, self.dict.words_root(), &codes, &message, start, starting_edges);
+            }
+        }
+        self.segmentations(&edges, max_words, work)
+    }
+
+    /// Dictionary words starting at each position of the letters, as (end,
+    /// word index) pairs. When a word is listed more than once, the most
+    /// frequent listing is used.
+    fn word_edges(&self, letters: &[char], min_len: usize) -> Vec<Vec<(usize, usize)>> {
+        let mut edges = vec![Vec::new(); letters.len()];
+        for (start, starting_edges) in edges.iter_mut().enumerate() {
+            let mut node = self.dict.words_root();
+            for (end, &letter) in letters.iter().enumerate().skip(start) {
+                node = match node.child(letter) {
+                    Some(child) => child,
+                    None => break,
+                };
+                if end + 1 - start < min_len {
+                    continue;
+                }
+                if let Some(&idx) = node.words().iter().max_by_key(|&&idx| self.dict.frequency(idx)) {
+                    starting_edges.push((end + 1, idx));
+                }
+            }
+        }
+        edges
+    }
+
+    /// Log-probability of a word, smoothed so words without a frequency still
+    /// count for something.
+    fn word_weight(&self, idx: usize, total: f64) -> f64 {
+        ((self.dict.frequency(idx) + 1) as f64 / total).ln()
+    }
+
+    fn segment_letters(letters: &str) -> Vec<char> {
+        letters.chars()
+            .filter(|c| c.is_alphabetic())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+
+    /// Every way of splitting the letters into at most max_words dictionary
+    /// words of at least min_len letters, most probable first. A max_words of
+    /// 0 means no limit.
+    pub fn segment(&self, letters: &str, max_words: usize, min_len: usize) -> Vec<String> {
+        self.segment_within(letters, max_words, min_len, &Work::unlimited())
+    }
+
+    /// Like `segment`, ranking only the segmentations found before the work
+    /// runs out.
+    pub fn segment_within(&self, letters: &str, max_words: usize, min_len: usize, work: &Work) -> Vec<String> {
+        let letters = Sifter::segment_letters(letters);
+        if letters.is_empty() {
+            return vec![];
+        }
+        self.segmentations(&self.word_edges(&letters, min_len), max_words, work)
+    }
+
+    /// Every path of at most max_words words through the edges from the start
+    /// to the end, most probable first. A max_words of 0 means no limit.
+    fn segmentations(&self, edges: &[Vec<(usize, usize)>], max_words: usize, work: &Work) -> Vec<String> {
+        let end = edges.len();
+        if end == 0 {
+            return vec![];
+        }
+        let max_words = if max_words == 0 { end } else { max_words };
+
+        // fewest words needed to finish from each position, to prune dead ends
+        let mut min_words = vec![usize::MAX; end + 1];
+        min_words[end] = 0;
+        for pos in (0..end).rev() {
+            min_words[pos] = edges[pos].iter()
+                .map(|&(end, _)| min_words[end].saturating_add(1))
+                .min()
+                .unwrap_or(usize::MAX);
+        }
+
+        let mut paths = Vec::new();
+        segment_walk(edges, &min_words, 0, max_words, &mut Vec::new(), &mut paths, work);
+        let total = (self.dict.total_frequency() + self.dict.word_count() as u64) as f64;
+        let mut scored: Vec<(f64, String)> = paths.into_iter()
+            .map(|path| {
+                let score = path.iter().map(|&idx| self.word_weight(idx, total)).sum();
+                let words: Vec<&str> = path.iter().map(|&idx| self.dict.word(idx)).collect();
+                (score, words.join(" "))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap().then_with(|| a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, segmentation)| segmentation).collect()
+    }
+
+    /// The most probable segmentation of the letters, found without listing
+    /// every segmentation.
+    pub fn best_segment(&self, letters: &str, max_words: usize, min_len: usize) -> Option<String> {
+        let letters = Sifter::segment_letters(letters);
+        if letters.is_empty() {
+            return None;
+        }
+        let max_words = if max_words == 0 { letters.len() } else { max_words };
+        let edges = self.word_edges(&letters, min_len);
+        let total = (self.dict.total_frequency() + self.dict.word_count() as u64) as f64;
+
+        // best[pos][k]: best (score, end, word) finishing from pos in at most k words
+        let mut best: Vec<Vec<Option<(f64, usize, usize)>>> = vec![vec![None; max_words + 1]; letters.len() + 1];
+        for pos in (0..letters.len()).rev() {
+            for k in 1..=max_words {
+                for &(end, idx) in &edges[pos] {
+                    let rest = if end == letters.len() {
+                        0.0
+                    } else {
+                        match best[end][k - 1] {
+                            Some((score, _, _)) => score,
+                            None => continue,
+                        }
+                    };
+                    let score = self.word_weight(idx, total) + rest;
+                    if best[pos][k].is_none_or(|(best_score, _, _)| score > best_score) {
+                        best[pos][k] = Some((score, end, idx));
+                    }
+                }
+            }
+        }
+
+        let mut words = Vec::new();
+        let (mut pos, mut k) = (0, max_words);
+        while pos < letters.len() {
+            let (_, end, idx) = best[pos][k]?;
+            words.push(self.dict.word(idx));
+            pos = end;
+            k -= 1;
+        }
+        Some(words.join(" "))
+    }
+
+    /// Words that can be traced through adjacent cells of the grid, each with
+    /// the first path found for it.
+    pub fn grid(&self, grid: &Grid, options: &GridOptions) -> Vec<(&str, Vec<(usize, usize)>)> {
+        self.grid_within(grid, options, &Work::unlimited())
+    }
+
+    /// Like `grid`, stopping once the work runs out.
+    pub fn grid_within(&self, grid: &Grid, options: &GridOptions, work: &Work) -> Vec<(&str, Vec<(usize, usize)>)> {
+        let mut search = GridSearch {
+            dict: &self.dict, grid, options, work, path: Vec::new(), found: HashMap::new(), capped: false,
+        };
+        for start in grid.cells() {
+            if options.straight {
+                for &direction in options.directions() {
+                    search.walk(self.dict.words_root(), start, Some(direction));
+                }
+            } else {
+                search.walk(self.dict.words_root(), start, None);
+            }
+        }
+        let mut results: Vec<(&str, Vec<(usize, usize)>)> = search.found.into_iter().collect();
+        results.sort();
+        results
+    }
+
+    /// Fills of the crossword consistent with every crossing, each giving one
+    /// word per slot in clue order. Stops after `limit` fills.
+    pub fn fill(&self, crossword: &Crossword, limit: usize) -> Solution<'_> {
+        self.fill_within(crossword, limit, &Work::unlimited())
+    }
+
+    /// Like `fill`, stopping once the work runs out.
+    pub fn fill_within(&self, crossword: &Crossword, limit: usize, work: &Work) -> Solution<'_> {
+        let slots = crossword.slots();
+        let candidates = slots.iter()
+            .map(|slot| {
+                let mut words: Vec<&str> = self.dict.lookup(&crossword.pattern(slot)).into_iter().collect();
+                words.sort_unstable();
+                words
+            })
+            .collect();
+        crossword::solve_within(&slots, candidates, limit, work)
+    }
+
+    /// Words of the given length that would have given every guess its
+    /// feedback.
+    pub fn wordle(&self, length: usize, guesses: &[Guess]) -> Vec<&str> {
+        let mut words: Vec<&str> = self.dict.lookup(&wordle::to_pattern(length, guesses)).into_iter()
+            .filter(|word| wordle::is_consistent(word, guesses))
+            .collect();
+        words.sort_unstable();
+        words
+    }
+
+    /// Next guesses ranked by the information they'd give about the remaining
+    /// candidates, best first. Only candidates are ranked unless `any_word` is
+    /// set, in which case every word of the length is.
+    pub fn wordle_suggestions(&self, length: usize, guesses: &[Guess], any_word: bool) -> Vec<(&str, f64)> {
+        self.wordle_suggestions_within(length, guesses, any_word, &Work::unlimited())
+    }
+
+    /// Like `wordle_suggestions`, ranking only the guesses scored before the
+    /// work runs out.
+    pub fn wordle_suggestions_within(&self, length: usize, guesses: &[Guess], any_word: bool,
+                                     work: &Work) -> Vec<(&str, f64)> {
+        let candidates = self.wordle(length, guesses);
+        let pool = if any_word {
+            self.dict.lookup(&".".repeat(length)).into_iter().collect()
+        } else {
+            candidates.clone()
+        };
+        let mut ranked: Vec<(&str, f64)> = pool.into_iter()
+            .take_while(|_| work.has_time())
+            .map(|word| (word, wordle::expected_information(word, &candidates)))
+            .collect();
+        let is_candidate: HashSet<&str> = candidates.iter().cloned().collect();
+        ranked.sort_by(|(a, a_bits), (b, b_bits)| {
+            b_bits.total_cmp(a_bits)
+                .then_with(|| is_candidate.contains(b).cmp(&is_candidate.contains(a)))
+                .then_with(|| a.cmp(b))
+        });
+        ranked
+    }
+
+    /// Spelling Bee words: at least `min_len` long, using only the given
+    /// letters (as often as they like) and always the center one. Each word
+    /// comes with whether it's a pangram, using every letter.
+    pub fn spelling_bee(&self, letters: &str, center: char, min_len: usize) -> Vec<(&str, bool)> {
+        self.spelling_bee_within(letters, center, min_len, &Work::unlimited())
+    }
+
+    /// Like `spelling_bee`, stopping once the work runs out.
+    pub fn spelling_bee_within(&self, letters: &str, center: char, min_len: usize, work: &Work) -> Vec<(&str, bool)> {
+        let others: Vec<char> = sort_letters(letters).chars().dedup().filter(|&letter| letter != center).collect();
+        let pangrams: HashSet<&str> = self.bank(&format!("{}{}", letters, center)).into_iter().collect();
+        let words = (0..=others.len())
+            .flat_map(|size| others.iter().combinations(size))
+            .flat_map(|subset| {
+                let mut subset_letters: String = subset.into_iter().collect();
+                subset_letters.push(center);
+                self.bank(&subset_letters)
+            });
+        let mut results: Vec<(&str, bool)> = work.collect_matching(words, |word| word.chars().count() >= min_len)
+            .into_iter()
+            .map(|word| (word, pangrams.contains(word)))
+            .collect();
+        results.sort_unstable();
+        results
+    }
+
+    /// Words that can be spelled around a Letter Boxed box: at least three
+    /// letters long, with no two letters in a row from the same side.
+    pub fn letter_boxed_words(&self, letter_box: &LetterBox) -> Vec<&str> {
+        let mut results = Vec::new();
+        walk_sides(self.dict.words_root(), letter_box, None, 0, &mut results);
+        let mut words: Vec<&str> = results.into_iter().map(|idx| self.dict.word(idx)).collect();
+        words.sort_unstable();
+        words
+    }
+
+    /// The shortest chains of Letter Boxed words, up to `max_words`, that use
+    /// every letter in the box.
+    pub fn letter_boxed(&self, letter_box: &LetterBox, max_words: usize) -> Vec<Vec<&str>> {
+        let words = self.letter_boxed_words(letter_box);
+        let mut chains = letterbox::shortest_chains(letter_box, &words, max_words);
+        chains.sort_unstable();
+        chains
+    }
+
+    /// Whether the word is in the dictionary with the given tag, or at all
+    /// for the tag "*".
+    pub fn has_tag(&self, word: &str, tag: &str) -> bool {
+        self.dict.has_tag(word, tag)
+    }
+
+    /// How common a result is in the word list. A phrase is only as common as
+    /// its least common word.
+    pub fn frequency(&self, result: &str) -> u64 {
+        result.split_whitespace()
+            .map(|word| self.dict.word_frequency(word))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Words spelled by picking letters, in order, out of the given ones.
+    pub fn subsequence(&self, letters: &str, gaps: &Gaps) -> Vec<&str> {
+        let letters: Vec<char> = letters.to_lowercase().chars().collect();
+        let mut results = HashSet::new();
+        sequence::walk_subsequences(self.dict.words_root(), &letters, gaps, &[], &mut results);
+        self.sorted_words(results)
+    }
+
+    /// Words containing the given letters in order, though not necessarily
+    /// together. Kangaroo words must not contain them together.
+    pub fn supersequence(&self, letters: &str, gaps: &Gaps, kangaroo: bool) -> Vec<&str> {
+        let letters = letters.to_lowercase();
+        if letters.is_empty() {
+            return vec![];
+        }
+        let chars: Vec<char> = letters.chars().collect();
+        let mut results = HashSet::new();
+        sequence::walk_supersequences(self.dict.words_root(), &chars, gaps, &[(0, 0)], &mut results);
+        let mut words = self.sorted_words(results);
+        if kangaroo {
+            words.retain(|word| !word.contains(&letters));
+        }
+        words
+    }
+
+    fn sorted_words(&self, indices: HashSet<usize>) -> Vec<&str> {
+        let mut words: Vec<&str> = indices.into_iter().map(|idx| self.dict.word(idx)).collect();
+        words.sort_unstable();
+        words.dedup();
+        words
+    }
+
     pub fn bank(&self, letters: &str) -> Vec<&str> {
         let mut plus_pattern = String::new();
         for letter in sort_letters(letters).chars().dedup() {
@@ -160,13 +881,17 @@ impl Sifter {
     }
 
     pub fn change(&self, letters: &str, n: usize) -> Vec<&str> {
+        self.change_within(letters, n, &Work::unlimited())
+    }
+
+    /// Like `change`, looking up patterns only until the work runs out.
+    pub fn change_within(&self, letters: &str, n: usize, work: &Work) -> Vec<&str> {
         if n > letters.len() {
             return vec![];
         }
         let mut results = HashSet::new();
-        for combo in all_replaced_wildcards(letters, n) {
-            results.extend(self.dict.lookup(&combo));
-        }
+        let combos = all_replaced_wildcards(letters, n, work);
+        results.extend(self.lookup_each(&combos, work, |dict, combo| dict.lookup(combo)));
         results.remove(letters);
         results.into_iter().collect()
     }
@@ -176,6 +901,7 @@ impl Sifter {
 mod tests {
     use super::*;
     use crate::test_utils::assert_set_equality;
+    use crate::budget::Budget;
 
     fn test_sifter() -> Sifter {
         Sifter::new_from_words_file("test_data/dict").unwrap()
@@ -201,6 +927,24 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_sum() {
+        let sifter = test_sifter();
+        let any = Regex::new(".*").unwrap();
+        assert_set_equality(sifter.sum(&any, &ScoreScheme::A1Z26, &(47..=47)), vec![
+            ("hose", 47),
+            ("shoe", 47),
+        ]);
+        assert_set_equality(sifter.sum(&Regex::new("h.*").unwrap(), &ScoreScheme::A1Z26, &(47..=47)), vec![
+            ("hose", 47),
+        ]);
+        assert_set_equality(sifter.sum(&any, &ScoreScheme::Scrabble, &(0..=4)), vec![
+            ("tear", 4),
+            ("rose", 4),
+            ("ross", 4),
+        ]);
+    }
+
     #[test]
     fn test_transpose_delete() {
         let sifter = test_sifter();
@@ -227,14 +971,14 @@ mod tests {
 
     #[test]
     fn test_all_replaced_wildcards() {
-        assert_set_equality(all_replaced_wildcards("aa", 0), vec![
+        assert_set_equality(all_replaced_wildcards("aa", 0, &Work::unlimited()), vec![
             "aa".to_string(),
         ]);
-        assert_set_equality(all_replaced_wildcards("aa", 1), vec![
+        assert_set_equality(all_replaced_wildcards("aa", 1, &Work::unlimited()), vec![
             ".a".to_string(),
             "a.".to_string(),
         ]);
-        assert_set_equality(all_replaced_wildcards("aaa", 2), vec![
+        assert_set_equality(all_replaced_wildcards("aaa", 2, &Work::unlimited()), vec![
             "..a".to_string(),
             "a..".to_string(),
             ".a.".to_string(),
@@ -243,10 +987,10 @@ mod tests {
 
     #[test]
     fn test_all_deletes() {
-        assert_set_equality(all_deletes("abc", 0), vec![
+        assert_set_equality(all_deletes("abc", 0, &Work::unlimited()), vec![
             "abc".to_string(),
         ]);
-        assert_set_equality(all_deletes("abc", 1), vec![
+        assert_set_equality(all_deletes("abc", 1, &Work::unlimited()), vec![
             "bc".to_string(),
             "ab".to_string(),
             "ac".to_string(),
@@ -255,10 +999,10 @@ mod tests {
 
     #[test]
     fn test_all_added_wildcards() {
-        assert_set_equality(all_added_wildcards("aa", 0), vec![
+        assert_set_equality(all_added_wildcards("aa", 0, &Work::unlimited()), vec![
             "aa".to_string(),
         ]);
-        assert_set_equality(all_added_wildcards("aa", 3), vec![
+        assert_set_equality(all_added_wildcards("aa", 3, &Work::unlimited()), vec![
             "...aa".to_string(),
             "..a.a".to_string(),
             "..aa.".to_string(),
@@ -305,6 +1049,265 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_behead_and_curtail() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.behead("smothers", 1), vec!["mothers"]);
+        assert_set_equality(sifter.behead("ateretreat", 5), vec!["treat"]);
+        assert_set_equality(sifter.behead("hose", 5), vec![]);
+        assert_set_equality(sifter.curtail("heroes", 2), vec!["hero"]);
+        assert_set_equality(sifter.curtail("shoes", 1), vec!["shoe"]);
+        assert_set_equality(sifter.curtail("hose", 5), vec![]);
+        assert_set_equality(sifter.curtail("hoseé", 1), vec!["hose"]);
+    }
+
+    #[test]
+    fn test_all_gutted() {
+        assert_set_equality(all_gutted("abcd", 1), vec![
+            "acd".to_string(),
+            "abd".to_string(),
+        ]);
+        assert_set_equality(all_gutted("abcd", 2), vec![
+            "ad".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_gut() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.gut("shoes", 1), vec!["shes"]);
+        assert_set_equality(sifter.gut("horsees", 1), vec!["horses"]);
+        assert_set_equality(sifter.gut("hose", 3), vec![]);
+    }
+
+    #[test]
+    fn test_delete_alternate() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.delete_alternate("hxoxsxe"), vec!["hose"]);
+        assert_set_equality(sifter.delete_alternate("xhxoxsxe"), vec!["hose"]);
+    }
+
+    #[test]
+    fn test_delete_at() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.delete_at("shoes", &[3]), vec!["shes"]);
+        assert_set_equality(sifter.delete_at("xhosex", &[1, 6]), vec!["hose"]);
+        assert_set_equality(sifter.delete_at("hose", &[0]), vec![]);
+        assert_set_equality(sifter.delete_at("hose", &[5]), vec![]);
+    }
+
+    #[test]
+    fn test_all_infixed_wildcards() {
+        assert_set_equality(all_infixed_wildcards("abc", 2), vec![
+            "a..bc".to_string(),
+            "ab..c".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_prefix_suffix_infix() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.prefix("others", 1), vec!["mothers"]);
+        assert_set_equality(sifter.suffix("hero", 2), vec!["heroes"]);
+        assert_set_equality(sifter.suffix("tea", 1), vec!["tear"]);
+        assert_set_equality(sifter.infix("homes", 1), vec!["holmes"]);
+        assert_set_equality(sifter.infix("mall", 1), vec![]);
+    }
+
+    #[test]
+    fn test_extract() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.extract("hxoxsxe", &Selection::Odd, Transform::Plain), vec!["hose"]);
+        assert_set_equality(sifter.extract("xhxxoxxsxxex", &Selection::Nth(3, 2), Transform::Plain), vec!["hose"]);
+        assert_set_equality(sifter.extract("hungry owls see eagles", &Selection::First, Transform::Plain), vec!["hose"]);
+        assert_set_equality(sifter.extract("every soft old hen", &Selection::First, Transform::Reverse), vec!["hose"]);
+        assert_set_equality(sifter.extract("sad hens or elves", &Selection::First, Transform::Anagram), vec![
+            "hose",
+            "shoe",
+        ]);
+        assert_set_equality(sifter.extract("hose", &Selection::Positions(vec![9]), Transform::Plain), vec![]);
+    }
+
+    #[test]
+    fn test_acrostic() {
+        let sifter = test_sifter();
+        assert_eq!(sifter.acrostic("hungry owls see eagles", false, None).unwrap(), vec![
+            ("hose".to_string(), "hungry owls see eagles".to_string()),
+        ]);
+        assert_eq!(sifter.acrostic("tah go mrs she", true, None).unwrap(), vec![
+            ("hose".to_string(), "tah go mrs she".to_string()),
+        ]);
+        assert_set_equality(sifter.acrostic("hat/sun ox,owl sea egg/elk", false, None).unwrap(), vec![
+            ("hose".to_string(), "hat ox sea egg".to_string()),
+            ("hose".to_string(), "hat ox sea elk".to_string()),
+            ("hose".to_string(), "hat owl sea egg".to_string()),
+            ("hose".to_string(), "hat owl sea elk".to_string()),
+        ]);
+        assert_set_equality(sifter.acrostic("hat/sun hat ox egg", false, Some("shoe")).unwrap(), vec![
+            ("shoe".to_string(), "sun hat ox egg".to_string()),
+        ]);
+        assert_eq!(sifter.acrostic("hat ox", false, Some("hose")).unwrap(), vec![]);
+        assert_eq!(sifter.acrostic("hat x sea egg", false, Some("hose")).unwrap(), vec![]);
+        assert_eq!(sifter.acrostic("", false, None).unwrap(), vec![]);
+        assert!(matches!(sifter.acrostic("hat ox sea egg", false, Some("hoxe")), Err(SiftError::UnknownWord)));
+    }
+
+    #[test]
+    fn test_keypad() {
+        let sifter = test_sifter();
+        assert_set_equality(sifter.keypad("4673"), vec!["hose"]);
+        assert_set_equality(sifter.keypad("7673"), vec!["rose"]);
+        assert_set_equality(sifter.keypad("1673"), vec![]);
+        assert_set_equality(sifter.keypad(""), vec![]);
+    }
+
+    #[test]
+    fn test_decode_unspaced() {
+        let sifter = test_sifter();
+        assert_eq!(sifter.decode_unspaced(Encoding::Morse, "....---....", 1), vec!["hose"]);
+        assert_eq!(sifter.decode_unspaced(Encoding::Morse, "....---...", 0), Vec::<String>::new());
+        assert_eq!(sifter.decode_unspaced(Encoding::Nato, "HotelOscarSierraEcho", 0), vec!["hose"]);
+        assert_eq!(sifter.decode_unspaced(Encoding::Morse, "", 0), Vec::<String>::new());
+        // hose and shoe, run together
+        let message = "....---.... / .......---.";
+        assert!(sifter.decode_unspaced(Encoding::Morse, message, 2).contains(&"hose shoe".to_string()));
+        assert!(sifter.decode_unspaced(Encoding::Morse, message, 1).is_empty());
+    }
+
+    #[test]
+    fn test_segment() {
+        let sifter = test_sifter();
+        assert_eq!(sifter.segment("hoseshoe", 0, 1), vec!["hose shoe"]);
+        assert_eq!(sifter.segment("Treat eater", 0, 1), vec!["treat eater"]);
+        assert_eq!(sifter.segment("smallmall", 1, 1), Vec::<String>::new());
+        assert_eq!(sifter.segment("smallmall", 0, 5), Vec::<String>::new());
+        assert_eq!(sifter.best_segment("smallmall", 2, 1), Some("small mall".to_string()));
+        assert_eq!(sifter.best_segment("smallmall", 1, 1), None);
+        assert_eq!(sifter.best_segment("", 0, 1), None);
+    }
+
+    #[test]
+    fn test_segment_frequencies() {
+        let sifter = Sifter::new_from_words("a 100\nb 50\nab\n".as_bytes());
+        assert_eq!(sifter.segment("ab", 0, 1), vec!["a b", "ab"]);
+        assert_eq!(sifter.best_segment("ab", 0, 1), Some("a b".to_string()));
+        assert_eq!(sifter.best_segment("ab", 1, 1), Some("ab".to_string()));
+        assert_eq!(sifter.segment("ab", 0, 2), vec!["ab"]);
+    }
+
+    #[test]
+    fn test_grid() {
+        let sifter = test_sifter();
+        let grid = Grid::parse("hos xer mal").unwrap();
+        let words = |options: &GridOptions| -> Vec<&str> {
+            sifter.grid(&grid, options).into_iter().map(|(word, _)| word).collect()
+        };
+        let options = GridOptions::default();
+        assert_eq!(words(&options), vec!["hero", "hose", "rose"]);
+        assert_eq!(sifter.grid(&grid, &options)[1], ("hose", vec![(0, 0), (0, 1), (0, 2), (1, 1)]));
+        assert_eq!(words(&GridOptions { diagonals: false, ..options }), Vec::<&str>::new());
+        assert_eq!(words(&GridOptions { min_len: 5, ..options }), Vec::<&str>::new());
+
+        let grid = Grid::parse("xhosex xesohx").unwrap();
+        let straight = GridOptions { straight: true, ..options };
+        assert_eq!(sifter.grid(&grid, &straight), vec![
+            ("hose", vec![(0, 1), (0, 2), (0, 3), (0, 4)]),
+        ]);
+        let wrapped: Vec<(&str, Vec<(usize, usize)>)> = sifter.grid(&Grid::parse("oseh").unwrap(), &GridOptions { wrap: true, ..straight });
+        assert_eq!(wrapped, vec![("hose", vec![(0, 3), (0, 0), (0, 1), (0, 2)])]);
+    }
+
+    #[test]
+    fn test_fill() {
+        let sifter = test_sifter();
+        let crossword = Crossword::parse("t... ###. ###. ###.").unwrap();
+        let solution = sifter.fill(&crossword, 10);
+        assert_eq!(solution.fills, vec![vec!["tear", "rose"], vec!["tear", "ross"]]);
+        assert_eq!(solution.candidates, vec![vec!["tear"], vec!["rose", "ross"]]);
+        let solution = sifter.fill(&crossword, 1);
+        assert_eq!(solution.fills, vec![vec!["tear", "rose"]]);
+        assert!(solution.limited);
+        let crossword = Crossword::parse("h... ###x ###. ###.").unwrap();
+        assert!(sifter.fill(&crossword, 10).fills.is_empty());
+    }
+
+    #[test]
+    fn test_wordle() {
+        let sifter = test_sifter();
+        let guesses = vec![Guess::parse("ross:ggyb").unwrap()];
+        assert_eq!(sifter.wordle(4, &guesses), Vec::<&str>::new());
+        let guesses = vec![Guess::parse("ross:gggb").unwrap()];
+        assert_eq!(sifter.wordle(4, &guesses), vec!["rose"]);
+        let guesses = vec![Guess::parse("shoe:yyyg").unwrap()];
+        assert_eq!(sifter.wordle(4, &guesses), vec!["hose"]);
+        let guesses = vec![Guess::parse("mall:bbbb").unwrap()];
+        assert_eq!(sifter.wordle(4, &guesses), vec!["hero", "hess", "hose", "rose", "ross", "shes", "shoe"]);
+    }
+
+    #[test]
+    fn test_wordle_suggestions() {
+        let sifter = test_sifter();
+        let guesses = vec![Guess::parse("mall:bbbb").unwrap()];
+        let ranked = sifter.wordle_suggestions(4, &guesses, false);
+        assert_eq!(ranked.len(), 7);
+        assert!(ranked.iter().all(|(_, bits)| (bits - 7f64.log2()).abs() < 1e-9));
+        let ranked: Vec<&str> = sifter.wordle_suggestions(4, &guesses, true).into_iter().map(|(word, _)| word).collect();
+        assert_eq!(ranked[7..], ["tear", "slam", "mall"]);
+    }
+
+    #[test]
+    fn test_spelling_bee() {
+        let sifter = test_sifter();
+        assert_eq!(sifter.spelling_bee("hoserx", 'h', 4), vec![
+            ("hero", false),
+            ("heroes", false),
+            ("hess", false),
+            ("horses", false),
+            ("hose", false),
+            ("shes", false),
+            ("shoe", false),
+            ("shores", false),
+        ]);
+        assert_eq!(sifter.spelling_bee("hoser", 'r', 5), vec![
+            ("heroes", true),
+            ("horses", true),
+            ("shores", true),
+        ]);
+    }
+
+    #[test]
+    fn test_letter_boxed() {
+        let sifter = test_sifter();
+        let letter_box = LetterBox::parse("sa ht or e").unwrap();
+        assert_eq!(sifter.letter_boxed_words(&letter_box), vec!["eater", "hose", "retreat", "shes", "shoe", "tear", "treat"]);
+        assert_eq!(sifter.letter_boxed(&letter_box, 3), vec![vec!["hose", "eater"], vec!["shoe", "eater"]]);
+        assert!(sifter.letter_boxed(&letter_box, 1).is_empty());
+    }
+
+    #[test]
+    fn test_subsequence() {
+        let sifter = test_sifter();
+        assert_eq!(sifter.subsequence("horses", &Gaps::default()), vec!["horses", "hose"]);
+        assert_eq!(sifter.subsequence("Heroes", &Gaps::default()), vec!["hero", "heroes"]);
+        assert_eq!(sifter.subsequence("horses", &Gaps { min: 1, max: None }), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_supersequence() {
+        let sifter = test_sifter();
+        let kangaroos = vec![
+            "heroes", "holders", "holmes", "horsely", "horses", "horsley", "housed", "rhodes", "shores", "thorsen",
+        ];
+        let mut all = kangaroos.clone();
+        all.push("hose");
+        all.sort_unstable();
+        assert_eq!(sifter.supersequence("hos", &Gaps::default(), false), all);
+        assert_eq!(sifter.supersequence("hos", &Gaps::default(), true), kangaroos);
+        assert_eq!(sifter.supersequence("hos", &Gaps { min: 0, max: Some(0) }, false), vec!["hose"]);
+        assert_eq!(sifter.supersequence("hos", &Gaps { min: 1, max: Some(2) }, false), vec!["heroes"]);
+        assert_eq!(sifter.supersequence("", &Gaps::default(), false), Vec::<&str>::new());
+    }
+
     #[test]
     fn test_bank() {
         let sifter = test_sifter();
@@ -329,4 +1332,41 @@ mod tests {
             "forbes",
         ]);
     }
+
+    #[test]
+    fn test_budget() {
+        let sifter = test_sifter();
+        let work = Budget { max_patterns: Some(3), ..Budget::default() }.start();
+        assert_eq!(all_added_wildcards("abcdefghij", 6, &work).len(), 3);
+        assert!(work.is_partial());
+        let work = Budget { max_patterns: Some(1), ..Budget::default() }.start();
+        assert_set_equality(sifter.delete_within("horses", 2, &work), vec![]);
+        assert!(work.is_partial());
+        let work = Budget { timeout: Some(std::time::Duration::from_secs(0)), ..Budget::default() }.start();
+        assert_set_equality(sifter.change_within("horses", 2, &work), vec![]);
+        assert!(work.is_partial());
+        let work = Work::unlimited();
+        sifter.add_within("horse", 2, &work);
+        assert!(!work.is_partial());
+
+        let capped = || Budget { max_results: Some(2), ..Budget::default() }.start();
+        let work = capped();
+        assert_eq!(sifter.regex_within(&Regex::new("h.*").unwrap(), &work).len(), 2);
+        assert!(work.is_partial());
+        let work = capped();
+        assert_eq!(sifter.grid_within(&Grid::parse("hos xer mal").unwrap(), &GridOptions::default(), &work).len(), 2);
+        assert!(work.is_partial());
+        let work = capped();
+        assert_eq!(sifter.acrostic_within("hat/hen/sun ox/owl sea egg/elk", false, None, &work).unwrap().len(), 2);
+        assert!(work.is_partial());
+        let work = capped();
+        assert_eq!(sifter.spelling_bee_within("hoserx", 'h', 4, &work).len(), 2);
+        assert!(work.is_partial());
+        let work = Budget { timeout: Some(std::time::Duration::from_secs(0)), ..Budget::default() }.start();
+        assert_eq!(sifter.segment_within("hoseshoe", 0, 1, &work), Vec::<String>::new());
+        assert!(work.is_partial());
+        let work = capped();
+        assert_eq!(sifter.segment_within("hoseshoe", 0, 1, &work), vec!["hose shoe"]);
+        assert!(!work.is_partial());
+    }
 }
@@ -0,0 +1,305 @@
+use crate::error::SiftError;
+use regex::Regex;
+
+/// How words filled into a regular expression are treated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quoting {
+    /// Words match themselves, with any regex metacharacters escaped.
+    Literal,
+    /// Words are spliced in as regex syntax.
+    Raw,
+}
+
+/// Which form of a stage's word a placeholder stands for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Form {
+    Whole,
+    Reversed,
+    Sorted,
+    /// Characters from the start up to (not including) the end, counting from
+    /// the back when negative, as in Python.
+    Slice(Option<isize>, Option<isize>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    /// A stage counted from 1, or the last stage when there's no number.
+    Word(Option<usize>, Form),
+}
+
+/// Text with placeholders for the words coming down a pipeline:
+///
+/// * `%` is the last stage's word, whatever follows it, so `%s` is the word
+///   with an 's' after it
+/// * `%{2}` is the second stage's word, counting from the first word piped in
+/// * `%{r}` and `%{s}` are the word reversed and with its letters sorted
+/// * `%{a:b}` is a slice of the word's letters, where either end may be left
+///   out and negative ends count from the back
+/// * these combine, as in `%{2r}` or `%{2[0:3]}`
+/// * `%{}` is the last stage's word and `%{%}` is a literal '%'
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+fn parse_bound(text: &str) -> Result<Option<isize>, SiftError> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    text.parse::<isize>().map(Some).map_err(|_| SiftError::InvalidTemplate)
+}
+
+/// Parses the stage number and form inside a placeholder's braces, returning
+/// them with how many characters they took up.
+fn parse_placeholder(chars: &[char]) -> Result<(Option<usize>, Form, usize), SiftError> {
+    let digits = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+    let stage = match digits {
+        0 => None,
+        _ => {
+            let number: String = chars[..digits].iter().collect();
+            match number.parse::<usize>() {
+                Ok(stage) if stage > 0 => Some(stage),
+                _ => return Err(SiftError::InvalidTemplate),
+            }
+        },
+    };
+    let (form, used) = match chars.get(digits) {
+        Some('r') => (Form::Reversed, 1),
+        Some('s') => (Form::Sorted, 1),
+        Some('[') => {
+            let close = chars[digits..].iter().position(|&c| c == ']').ok_or(SiftError::InvalidTemplate)?;
+            let inside: String = chars[digits + 1..digits + close].iter().collect();
+            let (start, end) = inside.split_once(':').ok_or(SiftError::InvalidTemplate)?;
+            (Form::Slice(parse_bound(start.trim())?, parse_bound(end.trim())?), close + 1)
+        },
+        _ => (Form::Whole, 0),
+    };
+    Ok((stage, form, digits + used))
+}
+
+/// Resolves a slice bound against a word of `len` characters.
+fn resolve_bound(bound: Option<isize>, len: usize, default: usize) -> usize {
+    match bound {
+        None => default,
+        Some(bound) if bound < 0 => len.saturating_sub(bound.unsigned_abs()),
+        Some(bound) => (bound as usize).min(len),
+    }
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, SiftError> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut idx = 0;
+        while idx < chars.len() {
+            if chars[idx] != '%' {
+                literal.push(chars[idx]);
+                idx += 1;
+                continue;
+            }
+            if chars.get(idx + 1) != Some(&'{') {
+                if !literal.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut literal)));
+                }
+                pieces.push(Piece::Word(None, Form::Whole));
+                idx += 1;
+                continue;
+            }
+            let close = chars[idx..].iter().position(|&c| c == '}').ok_or(SiftError::InvalidTemplate)?;
+            let inside = &chars[idx + 2..idx + close];
+            idx += close + 1;
+            if inside == ['%'] {
+                literal.push('%');
+                continue;
+            }
+            if !literal.is_empty() {
+                pieces.push(Piece::Text(std::mem::take(&mut literal)));
+            }
+            // A bare slice like `%{0:3}` is of the last stage's word.
+            let (stage, form) = if inside.contains(&':') && !inside.contains(&'[') {
+                let inside: String = inside.iter().collect();
+                let (start, end) = inside.split_once(':').ok_or(SiftError::InvalidTemplate)?;
+                (None, Form::Slice(parse_bound(start.trim())?, parse_bound(end.trim())?))
+            } else {
+                let (stage, form, used) = parse_placeholder(inside)?;
+                if used != inside.len() {
+                    return Err(SiftError::InvalidTemplate);
+                }
+                (stage, form)
+            };
+            pieces.push(Piece::Word(stage, form));
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Text(literal));
+        }
+        Ok(Template { pieces })
+    }
+
+    /// Fills every placeholder with the same text, whichever stage it stands
+    /// for.
+    pub fn fill(&self, filler: &str) -> String {
+        self.pieces.iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => text.as_str(),
+                Piece::Word(_, _) => filler,
+            })
+            .collect()
+    }
+
+    /// Fills in the placeholders from the words each stage of a pipeline
+    /// produced, first to last.
+    pub fn render(&self, stages: &[&str]) -> Result<String, SiftError> {
+        self.render_with(stages, str::to_string)
+    }
+
+    /// Like `render`, but passes each filled-in word through `quote`.
+    pub fn render_with<F: Fn(&str) -> String>(&self, stages: &[&str], quote: F) -> Result<String, SiftError> {
+        let mut rendered = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => rendered.push_str(text),
+                Piece::Word(stage, form) => {
+                    let word = match stage {
+                        Some(stage) => stages.get(stage - 1),
+                        None => stages.last(),
+                    };
+                    let word = word.ok_or(SiftError::MissingStage)?;
+                    rendered.push_str(&quote(&apply_form(word, *form)));
+                },
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+fn apply_form(word: &str, form: Form) -> String {
+    match form {
+        Form::Whole => word.to_string(),
+        Form::Reversed => word.chars().rev().collect(),
+        Form::Sorted => {
+            let mut letters: Vec<char> = word.chars().collect();
+            letters.sort_unstable();
+            letters.into_iter().collect()
+        },
+        Form::Slice(start, end) => {
+            let letters: Vec<char> = word.chars().collect();
+            let start = resolve_bound(start, letters.len(), 0);
+            let end = resolve_bound(end, letters.len(), letters.len());
+            letters.get(start..end.max(start)).unwrap_or_default().iter().collect()
+        },
+    }
+}
+
+/// A regular expression that may hold placeholders, which can't be compiled
+/// as written: `%{}s` isn't a valid regex until a word is filled in. Until
+/// then, each placeholder matches a literal '%'.
+#[derive(Clone, Debug)]
+pub struct RegexTemplate {
+    text: String,
+    regex: Regex,
+}
+
+impl RegexTemplate {
+    pub fn new(pattern: &str) -> Result<RegexTemplate, SiftError> {
+        let regex = Regex::new(&Template::parse(pattern)?.fill("%")).map_err(|_| SiftError::InvalidRegExp)?;
+        Ok(RegexTemplate { text: pattern.to_string(), regex })
+    }
+
+    /// The pattern as written, placeholders and all.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Fills in the placeholders and compiles the result.
+    pub fn substitute(&self, stages: &[&str], quoting: Quoting) -> Result<RegexTemplate, SiftError> {
+        let regex = substitute_regex(&self.text, stages, quoting)?;
+        Ok(RegexTemplate { text: regex.as_str().to_string(), regex })
+    }
+}
+
+/// Checks that text given on the command line is a valid template.
+pub fn validate(text: &str) -> Result<(), SiftError> {
+    Template::parse(text).map(|_| ())
+}
+
+/// Fills in the placeholders in the text from the words of each stage.
+pub fn substitute(text: &str, stages: &[&str]) -> Result<String, SiftError> {
+    Template::parse(text)?.render(stages)
+}
+
+/// Fills in the placeholders in a regular expression and compiles it.
+pub fn substitute_regex(pattern: &str, stages: &[&str], quoting: Quoting) -> Result<Regex, SiftError> {
+    let template = Template::parse(pattern)?;
+    let rendered = match quoting {
+        Quoting::Literal => template.render_with(stages, regex::escape)?,
+        Quoting::Raw => template.render(stages)?,
+    };
+    Regex::new(&rendered).map_err(|_| SiftError::InvalidRegExp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(text: &str, stages: &[&str]) -> String {
+        substitute(text, stages).unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(render("..%..%", &["foobar"]), "..foobar..foobar");
+        assert_eq!(render("%{1} %{2} %", &["hose", "shoe", "hoes"]), "hose shoe hoes");
+        assert_eq!(render("%{r}|%{s}|%{2r}", &["horse", "shoe"]), "eohs|ehos|eohs");
+        assert_eq!(render("%{0:3} %{2:} %{:-1} %{-2:} %{1[1:2]}", &["horse"]), "hor rse hors se o");
+        assert_eq!(render("%{4:2}%{9:}", &["horse"]), "");
+        assert_eq!(render("%{}s %{1r}x", &["hose"]), "hoses esohx");
+        assert_eq!(render("100%{%} %", &["hose"]), "100% hose");
+        // Only braces make a placeholder more than the last word.
+        assert_eq!(render("%s", &["mall"]), "malls");
+        assert_eq!(render("%[sd]", &["mall"]), "mall[sd]");
+        assert_eq!(render("%1%r%%", &["ab"]), "ab1abrabab");
+        assert_eq!(render("no placeholders", &[]), "no placeholders");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(Template::parse("%{[1:2}"), Err(SiftError::InvalidTemplate)));
+        assert!(matches!(Template::parse("%{[12]}"), Err(SiftError::InvalidTemplate)));
+        assert!(matches!(Template::parse("%{a:}"), Err(SiftError::InvalidTemplate)));
+        assert!(matches!(Template::parse("%{0}"), Err(SiftError::InvalidTemplate)));
+        assert!(matches!(Template::parse("%{1x}"), Err(SiftError::InvalidTemplate)));
+        assert!(matches!(Template::parse("%{1"), Err(SiftError::InvalidTemplate)));
+        assert!(matches!(substitute("%{3}", &["hose", "shoe"]), Err(SiftError::MissingStage)));
+        assert!(matches!(substitute("%", &[]), Err(SiftError::MissingStage)));
+    }
+
+    #[test]
+    fn test_substitute_regex() {
+        let regex = substitute_regex("^%.$", &["a.b"], Quoting::Literal).unwrap();
+        assert_eq!(regex.as_str(), r"^a\.b.$");
+        assert!(regex.is_match("a.bc"));
+        assert!(!regex.is_match("axbc"));
+        assert!(substitute_regex("^%.$", &["a.b"], Quoting::Raw).unwrap().is_match("axbc"));
+        assert!(substitute_regex("%", &["(["], Quoting::Literal).unwrap().is_match("(["));
+        assert!(matches!(substitute_regex("%", &["(["], Quoting::Raw), Err(SiftError::InvalidRegExp)));
+    }
+
+    #[test]
+    fn test_regex_template() {
+        let pattern = RegexTemplate::new("^%{}s$").unwrap();
+        assert_eq!(pattern.as_str(), "^%{}s$");
+        assert!(pattern.regex().is_match("%s"));
+        assert_eq!(pattern.substitute(&["hose"], Quoting::Literal).unwrap().as_str(), "^hoses$");
+        let pattern = RegexTemplate::new("%{1r}x").unwrap();
+        assert_eq!(pattern.substitute(&["ab", "cd"], Quoting::Literal).unwrap().as_str(), "bax");
+        assert!(matches!(RegexTemplate::new("(%"), Err(SiftError::InvalidRegExp)));
+        assert!(matches!(RegexTemplate::new("%{[1}"), Err(SiftError::InvalidTemplate)));
+        assert!(RegexTemplate::new("%[sd]").unwrap().substitute(&["mall"], Quoting::Literal).unwrap().regex().is_match("malls"));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Trie {
     root: TrieNode,
 }
@@ -17,10 +17,16 @@ impl Trie {
     pub fn add(&mut self, path: &str, idx: usize) {
         self.root.add(path, idx);
     }
+
+    /// The root node, for walks that need to follow the trie a letter at a
+    /// time rather than looking up a whole pattern.
+    pub fn root(&self) -> &TrieNode {
+        &self.root
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TrieNode {
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrieNode {
     letter: Option<char>,
     words: Vec<usize>,
     nodes: Vec<TrieNode>,
@@ -35,6 +41,19 @@ impl TrieNode {
         TrieNode { letter, words: Vec::new(), nodes: Vec::new() }
     }
 
+    pub fn child(&self, letter: char) -> Option<&TrieNode> {
+        self.nodes.iter().find(|node| node.letter == Some(letter))
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = (char, &TrieNode)> {
+        self.nodes.iter().filter_map(|node| node.letter.map(|letter| (letter, node)))
+    }
+
+    /// Indices of the words ending at this node.
+    pub fn words(&self) -> &[usize] {
+        &self.words
+    }
+
     fn add(&mut self, path: &str, idx: usize) {
         let mut path_letters = path.chars();
         match path_letters.next() {
@@ -69,6 +88,20 @@ impl TrieNode {
                 matches.extend(self.lookup(path_letters.as_str()));
                 matches
             },
+            Some('[') => {
+                let rest = path_letters.as_str();
+                let (class, rest) = match rest.find(']') {
+                    Some(end) => (&rest[..end], &rest[end + 1..]),
+                    None => return vec![],
+                };
+                let mut matches = Vec::new();
+                for node in &self.nodes {
+                    if node.letter.is_some_and(|letter| class.contains(letter)) {
+                        matches.extend(node.lookup(rest));
+                    }
+                }
+                matches
+            },
             Some('.') => {
                 let mut matches = Vec::new();
                 for node in &self.nodes {
@@ -82,7 +115,7 @@ impl TrieNode {
                         return node.lookup(path_letters.as_str());
                     }
                 }
-                return vec![];
+                vec![]
             },
         }
     }
@@ -139,4 +172,30 @@ mod tests {
         trie.add("abaa", 4);
         assert_eq!(trie.lookup("aa.a"), vec![1, 2, 3]);
     }
+
+    #[test]
+    fn test_walk() {
+        let mut trie = Trie::new();
+        trie.add("foo", 1);
+        trie.add("fob", 2);
+        let fo = trie.root().child('f').and_then(|node| node.child('o')).unwrap();
+        assert_eq!(fo.words().len(), 0);
+        let children: Vec<char> = fo.children().map(|(letter, _)| letter).collect();
+        assert_eq!(children, vec!['o', 'b']);
+        assert_eq!(fo.child('b').unwrap().words(), &[2]);
+        assert!(fo.child('x').is_none());
+    }
+
+    #[test]
+    fn test_classes() {
+        let mut trie = Trie::new();
+        trie.add("cat", 1);
+        trie.add("bat", 2);
+        trie.add("bit", 3);
+        trie.add("rat", 4);
+        assert_set_equality(trie.lookup("[bc]at"), vec![1, 2]);
+        assert_set_equality(trie.lookup("b[ai]t"), vec![2, 3]);
+        assert_set_equality(trie.lookup("[bcr].t"), vec![1, 2, 3, 4]);
+        assert_eq!(trie.lookup("[bc"), Vec::<usize>::new());
+    }
 }
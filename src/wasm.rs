@@ -0,0 +1,122 @@
+use crate::sifter::Sifter;
+use crate::query::Query;
+use crate::budget::Budget;
+use crate::template::Quoting;
+use wasm_bindgen::prelude::*;
+use std::io::Cursor;
+use std::time::Duration;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+#[wasm_bindgen]
+pub fn wasm_get_sifter(dict_data: Vec<u8>) -> Sifter {
+    let cursor = Cursor::new(dict_data);
+    Sifter::new_from_cache(cursor).unwrap()
+}
+
+#[wasm_bindgen]
+pub struct SifterResult {
+    result: Result<Vec<String>, String>,
+    count: usize,
+    partial: bool,
+}
+
+#[wasm_bindgen]
+impl SifterResult {
+    fn err(message: String) -> SifterResult {
+        SifterResult { result: Err(message), count: 0, partial: false }
+    }
+
+    fn ok(results: Vec<String>, partial: bool) -> SifterResult {
+        SifterResult { count: results.len(), result: Ok(results), partial }
+    }
+
+    fn counted(count: usize, partial: bool) -> SifterResult {
+        SifterResult { result: Ok(Vec::new()), count, partial }
+    }
+
+    /// Whether the query stopped early at one of its limits, so some results
+    /// may be missing.
+    pub fn partial(&self) -> bool {
+        self.partial
+    }
+
+    /// How many results there were, including those from `wasm_count`, which
+    /// doesn't keep the results themselves.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.result {
+            Ok(results) => results.len(),
+            Err(_) => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn to_string(&self, limit: usize) -> String {
+        let limit = limit.min(self.len());
+        match &self.result {
+            Ok(results) => results[0..limit].join("\n"),
+            Err(err) => err.clone(),
+        }
+    }
+}
+
+/// Limits on a query's work from JavaScript, where each may be left
+/// undefined.
+fn budget(max_patterns: Option<u32>, max_results: Option<u32>, timeout_ms: Option<u32>) -> Budget {
+    Budget {
+        max_patterns: max_patterns.map(|max| max as usize),
+        max_results: max_results.map(|max| max as usize),
+        timeout: timeout_ms.map(|ms| Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Piped words match literally in regexes unless `raw` is true, as with
+/// `sift --raw`.
+fn quoting(raw: Option<bool>) -> Quoting {
+    if raw.unwrap_or(false) { Quoting::Raw } else { Quoting::Literal }
+}
+
+/// Runs a query, stopping early with the results so far once it has tried
+/// `max_patterns` patterns in one command, found `max_results` results or run
+/// for `timeout_ms`.
+#[wasm_bindgen]
+pub fn wasm_sift(args: String, sifter: &Sifter, max_patterns: Option<u32>, max_results: Option<u32>, timeout_ms: Option<u32>, raw: Option<bool>) -> SifterResult {
+    let work = budget(max_patterns, max_results, timeout_ms).start();
+    match Query::parse(&args) {
+        Ok(query) => match query.run_within(sifter, &work, quoting(raw)) {
+            Ok(results) => SifterResult::ok(results, work.is_partial()),
+            Err(err) => SifterResult::err(format!("{:?}", err)),
+        },
+        Err(err) => SifterResult::err(err.render(&args)),
+    }
+}
+
+/// Counts the results of a query without formatting them, within the same
+/// limits as `wasm_sift`.
+#[wasm_bindgen]
+pub fn wasm_count(args: String, sifter: &Sifter, max_patterns: Option<u32>, timeout_ms: Option<u32>, raw: Option<bool>) -> SifterResult {
+    let work = budget(max_patterns, None, timeout_ms).start();
+    match Query::parse(&args) {
+        Ok(query) => match query.count_within(sifter, &work, quoting(raw)) {
+            Ok(count) => SifterResult::counted(count, work.is_partial()),
+            Err(err) => SifterResult::err(format!("{:?}", err)),
+        },
+        Err(err) => SifterResult::err(err.render(&args)),
+    }
+}
+
+#[wasm_bindgen]
+pub fn init_panic_hook() {
+    console_error_panic_hook::set_once();
+}
@@ -0,0 +1,169 @@
+use crate::error::SiftError;
+use std::collections::HashMap;
+
+/// Feedback for one letter of a guess.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mark {
+    /// Right letter, right position.
+    Green,
+    /// Letter is in the word, but elsewhere.
+    Yellow,
+    /// No more of this letter in the word.
+    Grey,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Guess {
+    pub word: Vec<char>,
+    pub marks: Vec<Mark>,
+}
+
+impl Guess {
+    /// Parses a guess written as `word:marks`, with a mark for each letter:
+    /// 'g' for green, 'y' for yellow, and 'b', 'x', '.' or '-' for grey.
+    pub fn parse(text: &str) -> Result<Guess, SiftError> {
+        let (word, marks) = text.split_once(':').ok_or(SiftError::InvalidGuess)?;
+        let word: Vec<char> = word.chars().map(|c| c.to_ascii_lowercase()).collect();
+        let marks = marks.chars()
+            .map(|mark| match mark.to_ascii_lowercase() {
+                'g' => Ok(Mark::Green),
+                'y' => Ok(Mark::Yellow),
+                'b' | 'x' | '.' | '-' => Ok(Mark::Grey),
+                _ => Err(SiftError::InvalidGuess),
+            })
+            .collect::<Result<Vec<Mark>, SiftError>>()?;
+        if word.is_empty() || word.len() != marks.len() || !word.iter().all(|c| c.is_ascii_lowercase()) {
+            return Err(SiftError::InvalidGuess);
+        }
+        Ok(Guess { word, marks })
+    }
+}
+
+/// The feedback a guess would get against an answer of the same length.
+/// Repeated letters are marked yellow only as many times as the answer has
+/// them, after greens have taken their share.
+pub fn feedback(guess: &[char], answer: &[char]) -> Vec<Mark> {
+    let mut marks = vec![Mark::Grey; guess.len()];
+    let mut unmatched: HashMap<char, usize> = HashMap::new();
+    for (idx, (&g, &a)) in guess.iter().zip(answer).enumerate() {
+        if g == a {
+            marks[idx] = Mark::Green;
+        } else {
+            *unmatched.entry(a).or_insert(0) += 1;
+        }
+    }
+    for (idx, g) in guess.iter().enumerate() {
+        if marks[idx] == Mark::Green {
+            continue;
+        }
+        if let Some(count) = unmatched.get_mut(g).filter(|count| **count > 0) {
+            *count -= 1;
+            marks[idx] = Mark::Yellow;
+        }
+    }
+    marks
+}
+
+/// A trie pattern of the letters each position could still hold, ignoring
+/// letter counts: greens fix a position, and yellows and greys rule a letter
+/// out of theirs. A letter that's only ever grey is ruled out everywhere.
+pub fn to_pattern(length: usize, guesses: &[Guess]) -> String {
+    let mut allowed = vec![[true; 26]; length];
+    let letter_idx = |letter: char| letter as usize - 'a' as usize;
+    for guess in guesses {
+        for (pos, (&letter, &mark)) in guess.word.iter().zip(&guess.marks).enumerate().take(length) {
+            match mark {
+                Mark::Green => {
+                    allowed[pos] = [false; 26];
+                    allowed[pos][letter_idx(letter)] = true;
+                },
+                Mark::Yellow => allowed[pos][letter_idx(letter)] = false,
+                Mark::Grey => {
+                    let elsewhere = guess.word.iter().zip(&guess.marks)
+                        .any(|(&other, &other_mark)| other == letter && other_mark != Mark::Grey);
+                    if elsewhere {
+                        allowed[pos][letter_idx(letter)] = false;
+                    } else {
+                        allowed.iter_mut().for_each(|letters| letters[letter_idx(letter)] = false);
+                    }
+                },
+            }
+        }
+    }
+    allowed.iter()
+        .map(|letters| {
+            let class: String = ('a'..='z').filter(|&letter| letters[letter_idx(letter)]).collect();
+            if class.len() == 26 {
+                ".".to_string()
+            } else {
+                format!("[{}]", class)
+            }
+        })
+        .collect()
+}
+
+/// Whether the word would have given every guess the feedback it got.
+pub fn is_consistent(word: &str, guesses: &[Guess]) -> bool {
+    let word: Vec<char> = word.chars().collect();
+    guesses.iter().all(|guess| guess.word.len() == word.len() && feedback(&guess.word, &word) == guess.marks)
+}
+
+/// The expected information, in bits, that guessing the word would give
+/// about which of the candidates is the answer.
+pub fn expected_information(guess: &str, candidates: &[&str]) -> f64 {
+    let guess: Vec<char> = guess.chars().collect();
+    let mut buckets: HashMap<Vec<Mark>, usize> = HashMap::new();
+    for candidate in candidates {
+        let answer: Vec<char> = candidate.chars().collect();
+        *buckets.entry(feedback(&guess, &answer)).or_insert(0) += 1;
+    }
+    let total = candidates.len() as f64;
+    buckets.values()
+        .map(|&count| count as f64 / total)
+        .fold(0.0, |bits, p| bits - p * p.log2())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Mark::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let guess = Guess::parse("Hose:gy.b").unwrap();
+        assert_eq!(guess.word, chars("hose"));
+        assert_eq!(guess.marks, vec![Green, Yellow, Grey, Grey]);
+        assert!(Guess::parse("hose").is_err());
+        assert!(Guess::parse("hose:gyb").is_err());
+        assert!(Guess::parse("hose:gybq").is_err());
+    }
+
+    #[test]
+    fn test_feedback() {
+        assert_eq!(feedback(&chars("hose"), &chars("shoe")), vec![Yellow, Yellow, Yellow, Green]);
+        assert_eq!(feedback(&chars("eerie"), &chars("there")), vec![Yellow, Grey, Yellow, Grey, Green]);
+        assert_eq!(feedback(&chars("ross"), &chars("rose")), vec![Green, Green, Green, Grey]);
+    }
+
+    #[test]
+    fn test_to_pattern() {
+        let guesses = vec![Guess::parse("ross:gggb").unwrap()];
+        assert_eq!(to_pattern(4, &guesses), "[r][o][s][abcdefghijklmnopqrtuvwxyz]");
+        let guesses = vec![Guess::parse("sh:yb").unwrap()];
+        assert_eq!(to_pattern(2, &guesses), "[abcdefgijklmnopqrtuvwxyz][abcdefgijklmnopqrstuvwxyz]");
+    }
+
+    #[test]
+    fn test_expected_information() {
+        assert_eq!(expected_information("rose", &["rose"]), 0.0);
+        assert_eq!(expected_information("ross", &["rose", "ross"]), 1.0);
+        assert_eq!(expected_information("shoe", &["hose", "shoe"]), 1.0);
+        assert_eq!(expected_information("mall", &["rose", "ross"]), 0.0);
+        let long = "ab".repeat(25);
+        assert_eq!(expected_information(&long, &[&long, &"ba".repeat(25)]), 1.0);
+    }
+}